@@ -3,113 +3,984 @@
 
 mod stack;
 
-use log::debug;
+use log::{debug, error, warn};
 use serde::{Deserialize, Serialize};
-use stack::{Execution, ProbedDimensions, StackIdentity};
+use stack::{Execution, StackIdentity};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State, Window};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 #[serde(rename_all = "UPPERCASE")]
 enum Status {
     Success,
     Failed,
+    // Output exists and the user has to confirm overwriting it
+    Conflict,
+    // Animation is estimated too large and the user has to confirm rendering it
+    Large,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 struct ProcessResult {
     status: Status,
     message: String,
+    // Output file, or the playlist of a segmented output
+    path: Option<String>,
+    warning: Option<String>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 struct ProbeResult {
     status: Status,
     message: String,
     probed: stack::Probed,
 }
 
+// Emitted for every file as a batch probe progresses
+#[derive(Serialize, Clone)]
+struct BatchProbeEvent {
+    input: String,
+    result: ProbeResult,
+}
+
+// Emitted once a stream exits without being stopped, with the end of FFmpeg's log
+#[derive(Serialize, Clone)]
+struct StreamEndedEvent {
+    result: ProcessResult,
+    log: Vec<String>,
+}
+
+// Emitted while a stack renders, `progress` runs from 0 to 1
+#[derive(Serialize, Clone)]
+struct StackProgressEvent {
+    progress: f64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ImportResult {
+    status: Status,
+    message: String,
+    positions: Vec<stack::Position>,
+    sources: Vec<stack::Source>,
+    // Keyed by path, includes files that failed probing
+    probes: HashMap<String, ProbeResult>,
+}
+
+#[derive(Serialize)]
+struct ArrangeResult {
+    status: Status,
+    message: String,
+    positions: Vec<stack::Position>,
+    stack: Option<stack::Stack>,
+}
+
+#[derive(Serialize)]
+struct ThumbnailResult {
+    status: Status,
+    message: String,
+    paths: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct StoryboardResult {
+    status: Status,
+    message: String,
+    path: Option<String>,
+}
+
+#[derive(Serialize)]
+struct WaveformResult {
+    status: Status,
+    message: String,
+    path: Option<String>,
+    peaks: Vec<f32>,
+}
+
+#[derive(Serialize)]
+struct ScenesResult {
+    status: Status,
+    message: String,
+    cuts: Vec<stack::Cut>,
+}
+
+#[derive(Serialize)]
+struct SilencesResult {
+    status: Status,
+    message: String,
+    silences: Vec<stack::Silence>,
+    // Maps onto the slider values of the tile
+    suggested: Option<[f64; 2]>,
+}
+
+#[derive(Serialize)]
+struct CacheResult {
+    status: Status,
+    message: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ExportResult {
+    status: Status,
+    message: String,
+    command: String,
+    warning: Option<String>,
+}
+
+// Playback speeds a tile may be set to
+const SPEED_RANGE: std::ops::RangeInclusive<f64> = 0.1..=10.0;
+
+// Size of visualizations when no tile has video to match
+const VISUALIZATION_SIZE: (u16, u16) = (640, 360);
+
+// Longest trimmed segment, in seconds, reversed or looped unless the GUI asks otherwise
+const REVERSE_LIMIT: f64 = 30.0;
+
+/// Prime the GUI input and pair every tile with its cached probe, sized as it will be stacked.
+/// Audio-only tiles are sized to match the first video tile.
+fn probe_primed(
+    tiles: stack::Tiles,
+    cache: &stack::ProbeCache,
+) -> Result<Vec<(stack::Primed, stack::Probed)>, String> {
+    let tiles = tiles
+        .prime()
+        .into_iter()
+        .map(|prime| {
+            if let Some(speed) = prime.speed {
+                if !SPEED_RANGE.contains(&speed) {
+                    return Err(format!(
+                        "Speed {}x of {} is outside {}x to {}x",
+                        speed,
+                        prime.path,
+                        SPEED_RANGE.start(),
+                        SPEED_RANGE.end()
+                    ));
+                }
+            }
+
+            if let Some(subtitles) = &prime.subtitles {
+                subtitles
+                    .validate()
+                    .map_err(|e| format!("Invalid subtitles for {}: {}", prime.path, e))?;
+            }
+
+            // Metadata comes from the cache rather than trusting what the GUI sends back
+            let probed = cache.fetch(&prime.path, || run_probe(&prime.path))?;
+
+            if probed.audio_only
+                && (prime.crop.is_some() || prime.presets.iter().any(|p| p.is_transposing()))
+            {
+                return Err(format!(
+                    "Unable to crop or rotate audio-only input {}",
+                    prime.path
+                ));
+            }
+
+            let probed = match prime.crop {
+                Some(rect) => probed.cropped(&rect).ok_or(format!(
+                    "Crop {} doesn't fit the {}x{} frame of {}",
+                    rect, probed.width, probed.height, prime.path
+                ))?,
+                None => probed,
+            };
+
+            // Rotating a quarter turn swaps the dimensions the stack is checked against
+            let mut probed = probed;
+            for preset in prime.presets.iter() {
+                preset
+                    .validate()
+                    .map_err(|e| format!("Invalid preset for {}: {}", prime.path, e))?;
+
+                if preset.is_transposing() {
+                    probed = probed.transposed();
+                }
+            }
+
+            Ok((prime, probed))
+        })
+        .collect::<Result<Vec<_>, String>>();
+
+    cache.flush();
+    let tiles = tiles?;
+
+    // Visualizations take the size of the first video tile so the stack lines up
+    let (width, height) = tiles
+        .iter()
+        .find(|(_, probed)| !probed.audio_only)
+        .map(|(_, probed)| (probed.width, probed.height))
+        .unwrap_or(VISUALIZATION_SIZE);
+
+    Ok(tiles
+        .into_iter()
+        .map(|(prime, probed)| match probed.audio_only {
+            true => (prime, probed.sized(width, height)),
+            false => (prime, probed),
+        })
+        .collect())
+}
+
+/// Directory inside the app cache for derived files, created on demand
+fn cache_subdir(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path_resolver()
+        .app_cache_dir()
+        .ok_or(String::from("No cache directory available"))?
+        .join(name);
+
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed creating cache directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Probe through the cache, writing a new result out right away
+fn fetch_probe(cache: &stack::ProbeCache, input: &str) -> Result<stack::Probed, String> {
+    let probed = cache.fetch(input, || run_probe(input));
+    cache.flush();
+    probed
+}
+
+fn run_probe(input: &str) -> Result<stack::Probed, String> {
+    stack::Probe::new(input)
+        .run()
+        .map_err(|e| format!("Failed probing {}: {}", input, e))
+}
+
+/// Warn when an animated output is likely too large to share
+fn size_warning(stacker: &stack::Stacker) -> Option<String> {
+    let size = stacker.estimated_size()?;
+    let (width, height) = stacker.dimensions();
+
+    (size > stack::LARGE_SIZE).then(|| {
+        let warning = format!(
+            "Estimated size is {:.1} MB for a {}x{} stack, consider a lower fps, width or trim",
+            size as f64 / (1024.0 * 1024.0),
+            width,
+            height
+        );
+        warn!("{}", warning);
+        warning
+    })
+}
+
+/// Group the GUI input and validate it against the probes before stacking
+fn prepare(
+    tiles: stack::Tiles,
+    cache: &stack::ProbeCache,
+    output: &stack::Destination,
+    reverse_limit: Option<f64>,
+) -> Result<stack::Stacker, String> {
+    let targets = [
+        output.animation.is_some(),
+        output.segmenting.is_some(),
+        output.streaming.is_some(),
+    ];
+    if targets.into_iter().filter(|set| *set).count() > 1 {
+        return Err(String::from(
+            "Choose only one of an animated, segmented or streamed output",
+        ));
+    }
+
+    if let Some(segmenting) = &output.segmenting {
+        if segmenting.segment_duration <= 0.0 {
+            return Err(String::from("Segment duration must be above zero"));
+        }
+
+        if segmenting.renditions.iter().any(|r| r.height == 0) {
+            return Err(String::from("Rendition height must be above zero"));
+        }
+    }
+
+    if let Some(animation) = &output.animation {
+        animation.validate()?;
+    }
+
+    if let Some(subtitles) = &output.subtitles {
+        subtitles.validate()?;
+    }
+
+    if let Some(watermark) = &output.watermark {
+        watermark.validate()?;
+    }
+
+    output.metadata.validate()?;
+
+    // Dimension checks below see the post-crop size
+    let tiles = probe_primed(tiles, cache)?;
+    let reverse_limit = reverse_limit.unwrap_or(REVERSE_LIMIT);
+
+    for (prime, probed) in tiles.iter() {
+        prime.check_playback(probed, reverse_limit)?;
+    }
+    let (primed, probes): (Vec<_>, Vec<_>) = tiles.iter().cloned().unzip();
+
+    debug!("Probes: {:#?}", probes);
+
+    primed.identify().check(&probes)?;
+
+    let mut stacker = stack::Stacker::new(tiles, output)
+        .map_err(|e| format!("Unable to create output: {}", e))?;
+    stacker.check_subtitles()?;
+
+    stacker.assemble();
+    debug!("Stacker CMD: {}", stacker);
+    debug!("Output duration: {:.2}s", stacker.duration());
+
+    Ok(stacker)
+}
+
 // Command needs to be async to handle "hanging" of GUI
 #[tauri::command(async)]
 fn process(
-    positions: Vec<stack::Position>,
-    sources: Vec<stack::Source>,
-    sliders: Vec<Option<stack::Slider>>,
-    probes: Vec<stack::Probed>,
-    output: String,
+    tiles: stack::Tiles,
+    output: stack::Destination,
+    reverse_limit: Option<f64>,
+    allow_large: Option<bool>,
+    window: Window,
+    cache: State<'_, stack::ProbeCache>,
 ) -> ProcessResult {
-    // Clean call ensures no empty items on grid.
-    let primed = stack::Group::new()
-        .add(positions)
-        .add(sources)
-        .add_optional(sliders)
-        .clean()
-        .prime();
+    // Processing blocks until FFmpeg exits, which a stream may never do
+    if output.streaming.is_some() {
+        return ProcessResult {
+            status: Status::Failed,
+            message: String::from("Stream targets are started with the stream command"),
+            path: None,
+            warning: None,
+        };
+    }
 
-    debug!("Probes: {:#?}", probes);
+    let mut stacker = match prepare(tiles, &cache, &output, reverse_limit) {
+        Ok(stacker) => stacker,
+        Err(message) => {
+            return ProcessResult {
+                status: Status::Failed,
+                message,
+                path: None,
+                warning: None,
+            }
+        }
+    };
 
-    match primed.identify() {
-        stack::Stack::Vertical => {
-            if !probes.is_same_width() {
-                return ProcessResult {
-                    status: Status::Failed,
-                    message: String::from("Unable to process vertical stack with mismatched width"),
-                };
+    if output.collision == stack::Collision::Ask && Path::new(&stacker.output_path()).exists() {
+        return ProcessResult {
+            status: Status::Conflict,
+            message: format!("{} already exists", stacker.output_path()),
+            path: Some(stacker.output_path()),
+            warning: None,
+        };
+    }
+
+    // Estimated up front so the user can back out before a long render
+    let size_warning = size_warning(&stacker);
+    if let (Some(warning), false) = (&size_warning, allow_large.unwrap_or_default()) {
+        return ProcessResult {
+            status: Status::Large,
+            message: warning.clone(),
+            path: Some(stacker.output_path()),
+            warning: Some(warning.clone()),
+        };
+    }
+
+    let message = match stacker.segmenting() {
+        Some(segmenting) => format!(
+            "Saved {} playlist to: {}",
+            segmenting.package,
+            stacker.output_path()
+        ),
+        None => format!("Saved stacked file to: {}", stacker.output_path()),
+    };
+
+    let started = Instant::now();
+    let rendered = stacker.run(|progress| {
+        if let Err(e) = window.emit("stack-progress", StackProgressEvent { progress }) {
+            error!("Failed emitting stack progress: {e}");
+        }
+    });
+
+    match rendered {
+        Ok(status) => {
+            // The render stands even when its report can't be written
+            let report = stacker.report(started.elapsed(), status);
+            let report = report.write(output.collision).map_err(|e| {
+                let warning = format!("Failed writing job report: {}", e);
+                error!("{}", warning);
+                warning
+            });
+
+            let warnings: Vec<String> =
+                [size_warning, report.err()].into_iter().flatten().collect();
+
+            ProcessResult {
+                status: Status::Success,
+                message,
+                path: Some(stacker.output_path()),
+                warning: (!warnings.is_empty()).then(|| warnings.join(". ")),
             }
         }
-        stack::Stack::Horizontal => {
-            if !probes.is_same_height() {
-                return ProcessResult {
-                    status: Status::Failed,
-                    message: String::from(
-                        "Unable to process horizontal stack with mismatched height",
-                    ),
-                };
+        Err(e) => ProcessResult {
+            status: Status::Failed,
+            message: format!("Stacking failed: {:#?}", e),
+            path: None,
+            warning: None,
+        },
+    }
+}
+
+/// Start pushing the stack to a network endpoint, returns once FFmpeg is running
+#[tauri::command(async)]
+fn stream(
+    tiles: stack::Tiles,
+    output: stack::Destination,
+    reverse_limit: Option<f64>,
+    app: AppHandle,
+    cache: State<'_, stack::ProbeCache>,
+    session: State<'_, stack::Session>,
+) -> ProcessResult {
+    let failed = |message: String| ProcessResult {
+        status: Status::Failed,
+        message,
+        path: None,
+        warning: None,
+    };
+
+    if output.streaming.is_none() {
+        return failed(String::from("No stream target given"));
+    }
+
+    let mut stacker = match prepare(tiles, &cache, &output, reverse_limit) {
+        Ok(stacker) => stacker,
+        Err(message) => return failed(message),
+    };
+
+    let generation = match session.start(stacker.assemble()) {
+        Ok(generation) => generation,
+        Err(e) => return failed(e.to_string()),
+    };
+
+    // Let the GUI know when this stream ends without being stopped,
+    // a stream started after it has a watcher of its own
+    std::thread::spawn(move || loop {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        let session = app.state::<stack::Session>();
+        if let Some(ended) = session.finished(generation) {
+            let result = ProcessResult {
+                status: match ended.status.success() {
+                    true => Status::Success,
+                    false => Status::Failed,
+                },
+                message: format!("Stream ended: {}", ended.status),
+                path: None,
+                warning: None,
+            };
+            let event = StreamEndedEvent {
+                result,
+                log: ended.log,
+            };
+            if let Err(e) = app.emit_all("stream-ended", event) {
+                error!("Failed emitting stream end: {e}");
             }
+            break;
         }
-        stack::Stack::X => {
-            if !probes.is_same_dimensions() {
-                return ProcessResult {
-                    status: Status::Failed,
-                    message: String::from("Unable to process X stack with mismatched dimensions"),
+
+        if !session.holds(generation) {
+            break;
+        }
+    });
+
+    ProcessResult {
+        status: Status::Success,
+        message: format!("Streaming to: {}", stacker.output_path()),
+        path: Some(stacker.output_path()),
+        warning: None,
+    }
+}
+
+/// Stop the running stream, if any
+#[tauri::command]
+fn stop_stream(session: State<'_, stack::Session>) -> ProcessResult {
+    match session.stop() {
+        true => ProcessResult {
+            status: Status::Success,
+            message: String::from("Stopped stream"),
+            path: None,
+            warning: None,
+        },
+        false => ProcessResult {
+            status: Status::Failed,
+            message: String::from("No stream is running"),
+            path: None,
+            warning: None,
+        },
+    }
+}
+
+/// Propose new grid positions for the tiles using the given strategy
+#[tauri::command(async)]
+fn arrange(
+    tiles: stack::Tiles,
+    strategy: stack::Strategy,
+    cache: State<'_, stack::ProbeCache>,
+) -> ArrangeResult {
+    match probe_primed(tiles, &cache).and_then(|tiles| stack::arrange(tiles, strategy)) {
+        Ok((positions, stack)) => ArrangeResult {
+            status: Status::Success,
+            message: format!("Arranged {} tiles", positions.len()),
+            positions,
+            stack: Some(stack),
+        },
+        Err(message) => ArrangeResult {
+            status: Status::Failed,
+            message,
+            positions: Vec::new(),
+            stack: None,
+        },
+    }
+}
+
+/// Extract frames at the given timestamps, or a strip of `count` evenly spaced frames tiled
+/// into one image, into the app cache
+#[tauri::command(async)]
+fn thumbnails(
+    input: String,
+    timestamps: Option<Vec<f64>>,
+    count: Option<usize>,
+    width: Option<u32>,
+    format: Option<stack::ImageFormat>,
+    app: AppHandle,
+    cache: State<'_, stack::ProbeCache>,
+) -> ThumbnailResult {
+    let failed = |message: String| ThumbnailResult {
+        status: Status::Failed,
+        message,
+        paths: Vec::new(),
+    };
+
+    let cache_dir = match cache_subdir(&app, "thumbnails") {
+        Ok(dir) => dir,
+        Err(message) => return failed(message),
+    };
+
+    let (width, format) = (width.unwrap_or(320), format.unwrap_or_default());
+
+    let timestamps = match timestamps {
+        Some(timestamps) => timestamps,
+        None => {
+            let count = count.unwrap_or(1).max(1);
+            let strip = fetch_probe(&cache, &input).and_then(|probed| {
+                stack::Thumbnail::strip(&input, probed.duration, count, width, format, &cache_dir)
+                    .map_err(|e| format!("Failed reading input: {}", e))
+            });
+
+            return match strip.and_then(|mut strip| {
+                if !strip.is_cached() {
+                    strip.extract()?;
+                }
+                Ok(strip.output_path())
+            }) {
+                Ok(path) => ThumbnailResult {
+                    status: Status::Success,
+                    message: format!("Extracted a strip of {} frames", count),
+                    paths: vec![path],
+                },
+                Err(message) => failed(message),
+            };
+        }
+    };
+
+    let jobs: Vec<(usize, f64)> = timestamps.into_iter().enumerate().collect();
+
+    let extracted = stack::pooled(jobs, |(idx, timestamp)| {
+        let mut thumbnail = stack::Thumbnail::new(&input, timestamp, width, format, &cache_dir)
+            .map_err(|e| format!("Failed reading input: {}", e))?;
+
+        if !thumbnail.is_cached() {
+            thumbnail.extract()?;
+        }
+
+        Ok((idx, thumbnail.output_path()))
+    })
+    .into_iter()
+    .collect::<Result<Vec<_>, String>>();
+
+    match extracted {
+        Ok(mut paths) => {
+            // Workers finish out of order
+            paths.sort_by_key(|(idx, _)| *idx);
+            ThumbnailResult {
+                status: Status::Success,
+                message: format!("Extracted {} thumbnails", paths.len()),
+                paths: paths.into_iter().map(|(_, path)| path).collect(),
+            }
+        }
+        Err(message) => failed(message),
+    }
+}
+
+/// Render a contact sheet of frames sampled from one input
+#[tauri::command(async)]
+fn storyboard(
+    input: String,
+    sheet: Option<stack::Sheet>,
+    output: Option<stack::Destination>,
+    app: AppHandle,
+    cache: State<'_, stack::ProbeCache>,
+) -> StoryboardResult {
+    let failed = |message: String| StoryboardResult {
+        status: Status::Failed,
+        message,
+        path: None,
+    };
+
+    let cache_dir = match cache_subdir(&app, "storyboards") {
+        Ok(dir) => dir,
+        Err(message) => return failed(message),
+    };
+
+    let probed = match fetch_probe(&cache, &input) {
+        Ok(probed) => probed,
+        Err(message) => return failed(message),
+    };
+
+    let sheet = sheet.unwrap_or_default();
+    let mut storyboard =
+        match stack::Storyboard::new(&input, probed.duration, sheet, output.as_ref(), &cache_dir) {
+            Ok(storyboard) => storyboard,
+            Err(message) => return failed(message),
+        };
+
+    let asking = output.is_some_and(|output| output.collision == stack::Collision::Ask);
+    if asking && Path::new(&storyboard.output_path()).exists() {
+        return StoryboardResult {
+            status: Status::Conflict,
+            message: format!("{} already exists", storyboard.output_path()),
+            path: Some(storyboard.output_path()),
+        };
+    }
+
+    match storyboard.execute() {
+        Ok(_) => StoryboardResult {
+            status: Status::Success,
+            message: format!("Saved storyboard to: {}", storyboard.output_path()),
+            path: Some(storyboard.output_path()),
+        },
+        Err(e) => failed(format!("Failed rendering storyboard: {:#?}", e)),
+    }
+}
+
+/// Render the audio waveform of an input as an image, or as peak data for the GUI to draw
+#[tauri::command(async)]
+fn waveform(
+    input: String,
+    width: u32,
+    height: Option<u32>,
+    mode: stack::WaveformMode,
+    app: AppHandle,
+) -> WaveformResult {
+    let failed = |message: String| WaveformResult {
+        status: Status::Failed,
+        message,
+        path: None,
+        peaks: Vec::new(),
+    };
+
+    match mode {
+        stack::WaveformMode::Image => {
+            let cache_dir = match cache_subdir(&app, "waveforms") {
+                Ok(dir) => dir,
+                Err(message) => return failed(message),
+            };
+
+            let mut waveform =
+                match stack::Waveform::new(&input, width, height.unwrap_or(120), &cache_dir) {
+                    Ok(waveform) => waveform,
+                    Err(e) => return failed(format!("Failed reading input: {}", e)),
                 };
+
+            if !waveform.is_cached() {
+                if let Err(message) = waveform.render() {
+                    return failed(message);
+                }
+            }
+
+            WaveformResult {
+                status: Status::Success,
+                message: String::from("Rendered waveform"),
+                path: Some(waveform.output_path()),
+                peaks: Vec::new(),
             }
         }
+        stack::WaveformMode::Peaks => match stack::Samples::new(&input).execute() {
+            Ok(pcm) => WaveformResult {
+                status: Status::Success,
+                message: String::from("Computed waveform peaks"),
+                path: None,
+                peaks: stack::peaks(&pcm, width as usize),
+            },
+            Err(e) => failed(format!("Failed decoding audio: {:#?}", e)),
+        },
     }
+}
 
-    let mut stacker = stack::Stacker::new(primed, &output);
-    debug!("Stacker CMD: {}", stacker);
+/// Detect shot boundaries so trims can snap to them
+#[tauri::command(async)]
+fn scenes(input: String, threshold: Option<f64>) -> ScenesResult {
+    let threshold = threshold.unwrap_or(stack::SCENE_THRESHOLD);
+
+    if !(0.0..=1.0).contains(&threshold) {
+        return ScenesResult {
+            status: Status::Failed,
+            message: String::from("Scene threshold must be between 0 and 1"),
+            cuts: Vec::new(),
+        };
+    }
 
-    match stacker.execute() {
-        Ok(_) => ProcessResult {
+    match stack::SceneDetect::new(&input, threshold).execute() {
+        Ok(stdout) => {
+            let cuts = stack::parse_scenes(&String::from_utf8_lossy(&stdout));
+            ScenesResult {
+                status: Status::Success,
+                message: format!("Detected {} scene changes", cuts.len()),
+                cuts,
+            }
+        }
+        Err(e) => ScenesResult {
+            status: Status::Failed,
+            message: format!("Scene detection failed: {:#?}", e),
+            cuts: Vec::new(),
+        },
+    }
+}
+
+/// Find silent intervals and suggest a trim that cuts dead air from head and tail
+#[tauri::command(async)]
+fn silences(
+    input: String,
+    noise: Option<f64>,
+    min_duration: Option<f64>,
+    padding: Option<f64>,
+    cache: State<'_, stack::ProbeCache>,
+) -> SilencesResult {
+    let failed = |message: String| SilencesResult {
+        status: Status::Failed,
+        message,
+        silences: Vec::new(),
+        suggested: None,
+    };
+
+    let probed = match fetch_probe(&cache, &input) {
+        Ok(probed) => probed,
+        Err(message) => return failed(message),
+    };
+
+    let noise = noise.unwrap_or(stack::SILENCE_NOISE);
+    let min_duration = min_duration.unwrap_or(stack::SILENCE_DURATION);
+
+    match stack::SilenceDetect::new(&input, noise, min_duration).execute() {
+        Ok(stderr) => {
+            let silences =
+                stack::parse_silences(&String::from_utf8_lossy(&stderr), probed.duration);
+            let suggested = stack::suggest_trim(&silences, probed.duration, padding.unwrap_or(0.5));
+
+            SilencesResult {
+                status: Status::Success,
+                message: format!("Detected {} silent intervals", silences.len()),
+                silences,
+                suggested: Some(suggested),
+            }
+        }
+        Err(e) => failed(format!("Silence detection failed: {:#?}", e)),
+    }
+}
+
+/// Assemble the FFmpeg command without executing it, optionally saving it as a script
+// Async as preparing probes uncached tiles
+#[tauri::command(async)]
+fn export(
+    tiles: stack::Tiles,
+    output: stack::Destination,
+    script: Option<String>,
+    reverse_limit: Option<f64>,
+    cache: State<'_, stack::ProbeCache>,
+) -> ExportResult {
+    let stacker = match prepare(tiles, &cache, &output, reverse_limit) {
+        Ok(stacker) => stacker,
+        Err(message) => {
+            return ExportResult {
+                status: Status::Failed,
+                message,
+                command: String::new(),
+                warning: None,
+            }
+        }
+    };
+
+    let command = stacker.to_string();
+    let warning = size_warning(&stacker);
+
+    match script.map(|path| stacker.write_script(&path)) {
+        None => ExportResult {
             status: Status::Success,
-            message: format!("Saved stacked file to: {}", stacker.output_path()),
+            message: String::from("Assembled FFmpeg command"),
+            command,
+            warning,
         },
-        Err(e) => ProcessResult {
+        Some(Ok(path)) => ExportResult {
+            status: Status::Success,
+            message: format!("Saved script to: {}", path.display()),
+            command,
+            warning,
+        },
+        Some(Err(e)) => ExportResult {
             status: Status::Failed,
-            message: format!("Stacking failed: {:#?}", e),
+            message: format!("Failed writing script: {}", e),
+            command,
+            warning,
         },
     }
 }
 
 #[tauri::command]
-fn probe(input: String) -> ProbeResult {
-    debug!("Input: {}", input);
+fn probe(input: String, cache: State<'_, stack::ProbeCache>) -> ProbeResult {
+    let result = probe_input(&input, &cache);
+    cache.flush();
+    result
+}
 
-    match stack::Probe::new(&input).execute() {
-        Ok(stdout) => match stack::Probed::build(&stdout) {
-            Ok(probed) => ProbeResult {
-                status: Status::Success,
-                message: String::from("Succesful probe"),
-                probed,
-            },
-            Err(e) => ProbeResult {
+/// Probe many inputs concurrently, streaming each result as a `probe-batch` event
+#[tauri::command(async)]
+fn probe_batch(
+    inputs: Vec<String>,
+    window: Window,
+    cache: State<'_, stack::ProbeCache>,
+) -> HashMap<String, ProbeResult> {
+    let cache = cache.inner();
+
+    // Written out once for the whole batch rather than by every worker
+    let results = stack::pooled(inputs, |input| {
+        let result = probe_input(&input, cache);
+
+        let event = BatchProbeEvent {
+            input: input.clone(),
+            result: result.clone(),
+        };
+        if let Err(e) = window.emit("probe-batch", event) {
+            error!("Failed emitting probe result: {e}");
+        }
+
+        (input, result)
+    });
+
+    cache.flush();
+    results.into_iter().collect()
+}
+
+/// Scan a directory for media files, probe them and propose a grid for the GUI
+#[tauri::command(async)]
+fn import_folder(
+    directory: String,
+    recursive: bool,
+    extensions: Option<Vec<String>>,
+    cache: State<'_, stack::ProbeCache>,
+) -> ImportResult {
+    let extensions = extensions.unwrap_or_default();
+
+    let paths = match stack::scan(Path::new(&directory), recursive, &extensions) {
+        Ok(paths) => paths,
+        Err(e) => {
+            return ImportResult {
+                status: Status::Failed,
+                message: format!("Failed scanning directory: {}", e),
+                positions: Vec::new(),
+                sources: Vec::new(),
+                probes: HashMap::new(),
+            }
+        }
+    };
+
+    let cache = cache.inner();
+    let probes: HashMap<String, ProbeResult> = stack::pooled(paths.clone(), |path| {
+        let result = probe_input(&path, cache);
+        (path, result)
+    })
+    .into_iter()
+    .collect();
+    cache.flush();
+
+    // Only files that probed successfully make it onto the grid
+    let usable: Vec<String> = paths
+        .into_iter()
+        .filter(|path| matches!(probes[path].status, Status::Success))
+        .collect();
+
+    let (positions, sources) = match stack::arrange_grid(&usable) {
+        Ok(grid) => grid.into_iter().unzip(),
+        Err(message) => {
+            return ImportResult {
                 status: Status::Failed,
-                message: format!("Failed probing: {}", e),
-                probed: stack::Probed::default(),
+                message,
+                positions: Vec::new(),
+                sources: Vec::new(),
+                probes,
+            }
+        }
+    };
+
+    ImportResult {
+        status: Status::Success,
+        message: format!("Imported {} of {} files", usable.len(), probes.len()),
+        positions,
+        sources,
+        probes,
+    }
+}
+
+/// Turn persisting probes across launches on or off, the GUI keeps the setting
+#[tauri::command]
+fn persist_probes(
+    enabled: bool,
+    app: AppHandle,
+    cache: State<'_, stack::ProbeCache>,
+) -> CacheResult {
+    let store = match enabled {
+        true => match app.path_resolver().app_cache_dir() {
+            Some(dir) => Some(dir.join("probes.json")),
+            None => {
+                return CacheResult {
+                    status: Status::Failed,
+                    message: String::from("No cache directory available"),
+                }
+            }
+        },
+        false => None,
+    };
+
+    match cache.persist_to(store) {
+        Ok(count) => CacheResult {
+            status: Status::Success,
+            message: match enabled {
+                true => format!("Persisting {} cached probes", count),
+                false => String::from("Probes are only cached until the app closes"),
             },
         },
-        Err(e) => ProbeResult {
+        Err(e) => CacheResult {
+            status: Status::Failed,
+            message: format!("Failed updating probe cache: {}", e),
+        },
+    }
+}
+
+fn probe_input(input: &str, cache: &stack::ProbeCache) -> ProbeResult {
+    debug!("Input: {}", input);
+
+    match cache.fetch(input, || run_probe(input)) {
+        Ok(probed) => ProbeResult {
+            status: Status::Success,
+            message: String::from("Succesful probe"),
+            probed,
+        },
+        Err(message) => ProbeResult {
             status: Status::Failed,
-            message: format!("Failed executing FFprobe: {:#?}", e),
+            message,
             probed: stack::Probed::default(),
         },
     }
@@ -118,7 +989,28 @@ fn probe(input: String) -> ProbeResult {
 fn main() {
     env_logger::init();
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![process, probe])
+        .setup(|app| {
+            // Kept in memory only until the GUI opts into persisting
+            app.manage(stack::ProbeCache::new());
+            app.manage(stack::Session::new());
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            process,
+            stream,
+            stop_stream,
+            probe,
+            probe_batch,
+            persist_probes,
+            import_folder,
+            arrange,
+            thumbnails,
+            storyboard,
+            waveform,
+            scenes,
+            silences,
+            export
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
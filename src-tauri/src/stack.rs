@@ -1,14 +1,54 @@
+mod animating;
+mod arranging;
+mod caching;
+mod correcting;
+mod detecting;
+mod exporting;
+mod filtering;
 mod grouping;
+mod importing;
+mod naming;
+mod pooling;
 mod priming;
 mod probing;
+mod reporting;
+mod segmenting;
 mod stacking;
+mod storyboarding;
+mod streaming;
+mod subtitling;
+mod tagging;
+#[cfg(test)]
+mod testing;
+mod thumbnailing;
+mod visualizing;
+mod watermarking;
+mod waveforming;
 
-pub use grouping::{Group, Position, Slider, Source};
-pub use probing::{Probe, Probed, ProbedDimensions};
+pub use animating::LARGE_SIZE;
+pub use arranging::{arrange, Strategy};
+pub use caching::ProbeCache;
+pub use detecting::{
+    parse_scenes, parse_silences, suggest_trim, Cut, SceneDetect, Silence, SilenceDetect,
+    SCENE_THRESHOLD, SILENCE_DURATION, SILENCE_NOISE,
+};
+pub use grouping::{Position, Source, Tiles};
+pub use importing::{arrange_grid, scan};
+pub use naming::{Collision, Destination};
+pub use pooling::pooled;
+pub use priming::Primed;
+pub use probing::{Probe, Probed};
 pub use stacking::{Stack, StackIdentity, Stacker};
+pub use storyboarding::{Sheet, Storyboard};
+pub use streaming::Session;
+pub use thumbnailing::{ImageFormat, Thumbnail};
+pub use waveforming::{peaks, Samples, Waveform, WaveformMode};
 
 use log::error;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
 use std::process::{Command, Stdio};
+use std::thread::{self, JoinHandle};
 
 #[derive(Debug)]
 pub enum ExecuteError {
@@ -22,6 +62,23 @@ pub enum Handle {
     Err,
 }
 
+/// Drain a child's output on its own thread so the child never blocks on a full pipe,
+/// keeping only the last `lines` lines
+pub fn tail<R: Read + Send + 'static>(reader: R, lines: usize) -> JoinHandle<Vec<String>> {
+    thread::spawn(move || {
+        let mut kept = VecDeque::with_capacity(lines);
+
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            if kept.len() == lines {
+                kept.pop_front();
+            }
+            kept.push_back(line);
+        }
+
+        kept.into()
+    })
+}
+
 pub trait Execution {
     const HANDLE: Handle;
 
@@ -49,7 +106,11 @@ pub trait Execution {
         };
 
         match output.status.success() {
-            true => Ok(output.stdout),
+            // Hand back whichever stream was piped
+            true => match Self::HANDLE {
+                Handle::Out => Ok(output.stdout),
+                Handle::Err => Ok(output.stderr),
+            },
             false => {
                 let code = output
                     .status
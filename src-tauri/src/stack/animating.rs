@@ -0,0 +1,260 @@
+use super::filtering::{Chain, Filter, Pad};
+
+use core::fmt;
+use serde::Deserialize;
+
+/// Estimated size above which the GUI warns before sharing
+pub const LARGE_SIZE: u64 = 25 * 1024 * 1024;
+
+const DEFAULT_FPS: u8 = 15;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimatedFormat {
+    Gif,
+    Webp,
+    Apng,
+}
+
+impl AnimatedFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AnimatedFormat::Gif => "gif",
+            AnimatedFormat::Webp => "webp",
+            AnimatedFormat::Apng => "png",
+        }
+    }
+
+    // Rough compressed bytes per output pixel per frame, only used for estimates
+    fn density(&self) -> f64 {
+        match self {
+            AnimatedFormat::Gif => 0.12,
+            AnimatedFormat::Webp => 0.04,
+            AnimatedFormat::Apng => 0.35,
+        }
+    }
+}
+
+/// Dithering applied by `paletteuse` when reducing a GIF to 256 colours
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Dither {
+    Bayer,
+    Heckbert,
+    FloydSteinberg,
+    Sierra2,
+    #[default]
+    #[serde(rename = "sierra2_4a")]
+    Sierra2Lite,
+    None,
+}
+
+impl fmt::Display for Dither {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dither::Bayer => write!(f, "bayer"),
+            Dither::Heckbert => write!(f, "heckbert"),
+            Dither::FloydSteinberg => write!(f, "floyd_steinberg"),
+            Dither::Sierra2 => write!(f, "sierra2"),
+            Dither::Sierra2Lite => write!(f, "sierra2_4a"),
+            Dither::None => write!(f, "none"),
+        }
+    }
+}
+
+fn default_fps() -> u8 {
+    DEFAULT_FPS
+}
+
+/// Animated image output received from the GUI
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Animation {
+    pub format: AnimatedFormat,
+    #[serde(default = "default_fps")]
+    pub fps: u8,
+    // Keeps the stacked width when not set
+    #[serde(default)]
+    pub width: Option<u16>,
+    #[serde(default)]
+    pub dither: Dither,
+    // Number of times the animation plays, 0 plays forever
+    #[serde(default)]
+    pub plays: u16,
+}
+
+impl Animation {
+    /// Check values the GUI sent leave frames to render
+    pub fn validate(&self) -> Result<(), String> {
+        if self.fps == 0 {
+            return Err(String::from("Animation frame rate must be above zero"));
+        }
+
+        if self.width == Some(0) {
+            return Err(String::from("Animation width must be above zero"));
+        }
+
+        Ok(())
+    }
+
+    /// Chains turning the stacked video at `input` into animation frames at `output`.
+    /// GIF runs a palettegen pass over every frame before paletteuse maps them onto it.
+    pub fn chains(&self, input: Pad, output: Pad) -> Vec<Chain> {
+        let mut frames = Chain::new()
+            .inputs(vec![input])
+            .filter(Filter::new("fps").option("fps", self.fps));
+
+        if let Some(width) = self.width {
+            frames = frames.filter(
+                Filter::new("scale")
+                    .option("w", width)
+                    .option("h", -2)
+                    .option("flags", "lanczos"),
+            );
+        }
+
+        if self.format != AnimatedFormat::Gif {
+            return vec![frames.output(output)];
+        }
+
+        let (generate, apply, palette) =
+            (Pad::named("pg"), Pad::named("pu"), Pad::named("palette"));
+
+        vec![
+            frames
+                .filter(Filter::new("split"))
+                .output(generate.clone())
+                .output(apply.clone()),
+            Chain::new()
+                .inputs(vec![generate])
+                .filter(Filter::new("palettegen").option("stats_mode", "diff"))
+                .output(palette.clone()),
+            Chain::new()
+                .inputs(vec![apply, palette])
+                .filter(Filter::new("paletteuse").option("dither", self.dither))
+                .output(output),
+        ]
+    }
+
+    /// Muxer arguments placed ahead of the output path
+    pub fn args(&self) -> Vec<String> {
+        let args = match self.format {
+            // GIF counts repeats after the first play, -1 plays it once
+            AnimatedFormat::Gif => {
+                let repeats = match self.plays {
+                    0 => 0,
+                    1 => -1,
+                    n => n as i32 - 1,
+                };
+                vec![
+                    String::from("-f"),
+                    String::from("gif"),
+                    String::from("-loop"),
+                    repeats.to_string(),
+                ]
+            }
+            AnimatedFormat::Webp => vec![
+                String::from("-c:v"),
+                String::from("libwebp"),
+                String::from("-f"),
+                String::from("webp"),
+                String::from("-loop"),
+                self.plays.to_string(),
+            ],
+            AnimatedFormat::Apng => vec![
+                String::from("-f"),
+                String::from("apng"),
+                String::from("-plays"),
+                self.plays.to_string(),
+            ],
+        };
+
+        // Animated images carry no audio
+        [String::from("-an")].into_iter().chain(args).collect()
+    }
+
+    /// Rough output size in bytes for a stack of the given size and length
+    pub fn estimate_size(&self, width: u32, height: u32, duration: f64) -> u64 {
+        let (width, height) = match self.width {
+            Some(scaled) if width > 0 => {
+                (scaled as f64, height as f64 * scaled as f64 / width as f64)
+            }
+            _ => (width as f64, height as f64),
+        };
+
+        let frames = duration * self.fps as f64;
+        (width * height * frames * self.format.density()) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animation(format: AnimatedFormat) -> Animation {
+        Animation {
+            format,
+            fps: 10,
+            width: Some(480),
+            dither: Dither::Bayer,
+            plays: 0,
+        }
+    }
+
+    #[test]
+    fn it_builds_gif_palette() {
+        let chains = animation(AnimatedFormat::Gif).chains(Pad::named("v"), Pad::named("g"));
+        let result: Vec<String> = chains.iter().map(Chain::to_string).collect();
+
+        assert_eq!(
+            result,
+            [
+                "[v]fps=fps=10,scale=w=480:h=-2:flags=lanczos,split[pg][pu]",
+                "[pg]palettegen=stats_mode=diff[palette]",
+                "[pu][palette]paletteuse=dither=bayer[g]"
+            ]
+        );
+        assert_eq!(
+            animation(AnimatedFormat::Gif).args(),
+            ["-an", "-f", "gif", "-loop", "0"]
+        );
+    }
+
+    #[test]
+    fn it_sets_plays() {
+        let once = Animation {
+            plays: 1,
+            ..animation(AnimatedFormat::Gif)
+        };
+        assert_eq!(once.args(), ["-an", "-f", "gif", "-loop", "-1"]);
+
+        let apng = Animation {
+            plays: 3,
+            ..animation(AnimatedFormat::Apng)
+        };
+        assert_eq!(apng.args(), ["-an", "-f", "apng", "-plays", "3"]);
+    }
+
+    #[test]
+    fn it_validates() {
+        assert!(animation(AnimatedFormat::Gif).validate().is_ok());
+
+        let still = Animation {
+            fps: 0,
+            ..animation(AnimatedFormat::Gif)
+        };
+        assert!(still.validate().is_err());
+
+        let empty = Animation {
+            width: Some(0),
+            ..animation(AnimatedFormat::Webp)
+        };
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn it_estimates_size() {
+        let gif = animation(AnimatedFormat::Gif);
+        // Scaled to 480x270, 10 seconds at 10 fps
+        assert_eq!(gif.estimate_size(1920, 1080, 10.0), 1555200);
+    }
+}
@@ -0,0 +1,266 @@
+use super::grouping::Position;
+use super::priming::Primed;
+use super::probing::{Probed, ProbedDimensions};
+use super::stacking::{columns, Stack};
+
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// How tiles should be ordered on the grid
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    Name,
+    Duration,
+    Created,
+    Resolution,
+    /// Portrait clips ahead of landscape ones, on the same line. Giving each orientation its
+    /// own row would need tiles of different sizes in one xstack, which FFmpeg can't stack.
+    Portrait,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Chunk {
+    Number(u64),
+    Text(String),
+}
+
+/// Sort key where digit runs compare numerically, so `2.mov` sorts before `10.mov`
+fn natural_key(value: &str) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        let digit = c.is_ascii_digit();
+        let mut run = String::new();
+
+        while let Some(&c) = chars.peek() {
+            if c.is_ascii_digit() != digit {
+                break;
+            }
+            run.push(c);
+            chars.next();
+        }
+
+        chunks.push(match run.parse() {
+            Ok(number) if digit => Chunk::Number(number),
+            _ => Chunk::Text(run.to_lowercase()),
+        });
+    }
+
+    chunks
+}
+
+fn file_name(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+}
+
+fn created(path: &str) -> Option<SystemTime> {
+    let metadata = fs::metadata(path).ok()?;
+    metadata.created().or_else(|_| metadata.modified()).ok()
+}
+
+// Duration that ends up in the output, in milliseconds so it can be used as a sort key
+fn effective_duration(primed: &Primed, probed: &Probed) -> u64 {
+    (primed.output_duration(probed.duration) * 1000.0) as u64
+}
+
+fn is_portrait(probed: &Probed) -> bool {
+    probed.height > probed.width
+}
+
+/// Place tiles row by row in a near-square grid
+fn place(tiles: &[&(Primed, Probed)]) -> Vec<Position> {
+    let cols = columns(tiles.len());
+
+    tiles
+        .iter()
+        .enumerate()
+        .map(|(idx, (primed, _))| {
+            let (x, y) = ((idx % cols) as u8, (idx / cols) as u8);
+            Position::new(&primed.id, x, y)
+        })
+        .collect()
+}
+
+/// Lay the ordered tiles out as a grid when they share dimensions, otherwise as the one
+/// row or column their matching heights or widths allow, so the result can be stacked
+fn layout(tiles: &[&(Primed, Probed)]) -> Result<Vec<Position>, String> {
+    if tiles.len() > u8::MAX as usize + 1 {
+        return Err(format!("Too many tiles to arrange: {}", tiles.len()));
+    }
+
+    let probes: Vec<Probed> = tiles.iter().map(|(_, probed)| probed.clone()).collect();
+    let line = |horizontal: bool| {
+        tiles
+            .iter()
+            .enumerate()
+            .map(|(idx, (primed, _))| match horizontal {
+                true => Position::new(&primed.id, idx as u8, 0),
+                false => Position::new(&primed.id, 0, idx as u8),
+            })
+            .collect()
+    };
+
+    if probes.is_same_dimensions() {
+        Ok(place(tiles))
+    } else if probes.is_same_height() {
+        Ok(line(true))
+    } else if probes.is_same_width() {
+        Ok(line(false))
+    } else {
+        Err(String::from(
+            "Tiles share neither width nor height, crop them to a common size to stack them",
+        ))
+    }
+}
+
+/// Stack type that suits the shape of the arranged grid
+fn recommend(positions: &[Position]) -> Stack {
+    if positions.iter().all(|pos| pos.x() == 0) {
+        return Stack::Vertical;
+    }
+
+    if positions.iter().all(|pos| pos.y() == 0) {
+        return Stack::Horizontal;
+    }
+
+    Stack::X
+}
+
+/// Reorder primed tiles with their probes into new grid positions
+pub fn arrange(
+    mut tiles: Vec<(Primed, Probed)>,
+    strategy: Strategy,
+) -> Result<(Vec<Position>, Stack), String> {
+    // Name order is the tie breaker for every other strategy
+    tiles.sort_by_cached_key(|(primed, _)| natural_key(file_name(&primed.path)));
+
+    match strategy {
+        Strategy::Name => {}
+        Strategy::Duration => {
+            tiles.sort_by_key(|(primed, probed)| Reverse(effective_duration(primed, probed)))
+        }
+        Strategy::Created => tiles.sort_by_cached_key(|(primed, _)| created(&primed.path)),
+        Strategy::Resolution => {
+            tiles.sort_by_key(|(_, probed)| Reverse(probed.width as u32 * probed.height as u32))
+        }
+        // Mixed orientations only line up when heights or widths match
+        Strategy::Portrait => tiles.sort_by_key(|(_, probed)| !is_portrait(probed)),
+    }
+
+    let positions = layout(&tiles.iter().collect::<Vec<_>>())?;
+    let stack = recommend(&positions);
+    Ok((positions, stack))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::grouping::Identifiable;
+    use super::super::stacking::StackIdentity;
+    use super::*;
+
+    fn tile(id: &str, path: &str, duration: f64, width: u16, height: u16) -> (Primed, Probed) {
+        let primed = Primed {
+            id: id.into(),
+            path: path.into(),
+            ..Default::default()
+        };
+        let probed = Probed {
+            duration,
+            width,
+            height,
+            ..Default::default()
+        };
+
+        (primed, probed)
+    }
+
+    fn tiles() -> Vec<(Primed, Probed)> {
+        vec![
+            tile("1", "clips/10.mov", 5.0, 1920, 1080),
+            tile("2", "clips/2.mov", 30.0, 1920, 1080),
+            tile("3", "clips/1.mov", 10.0, 1920, 1080),
+            tile("4", "clips/3.mov", 20.0, 1920, 1080),
+        ]
+    }
+
+    // What `prepare` checks before stacking the arranged grid
+    fn assert_stackable(tiles: &[(Primed, Probed)], positions: &[Position], stack: Stack) {
+        let (mut primed, probes): (Vec<_>, Vec<_>) = tiles.iter().cloned().unzip();
+        for prime in primed.iter_mut() {
+            let position = positions.iter().find(|pos| pos.id() == prime.id).unwrap();
+            (prime.x, prime.y) = (position.x(), position.y());
+        }
+
+        assert_eq!(primed.identify(), stack);
+        assert!(stack.check(&probes).is_ok());
+    }
+
+    #[test]
+    fn it_sorts_naturally() {
+        let mut names = vec!["10.mov", "2.mov", "1.mov", "Clip 3.mov"];
+        names.sort_by_key(|name| natural_key(name));
+        assert_eq!(names, ["1.mov", "2.mov", "10.mov", "Clip 3.mov"]);
+    }
+
+    #[test]
+    fn it_arranges_by_name() {
+        let (positions, stack) = arrange(tiles(), Strategy::Name).unwrap();
+
+        assert_eq!(
+            positions,
+            [
+                Position::new("3", 0, 0),
+                Position::new("2", 1, 0),
+                Position::new("4", 0, 1),
+                Position::new("1", 1, 1),
+            ]
+        );
+        assert_eq!(stack, Stack::X);
+        assert_stackable(&tiles(), &positions, stack);
+    }
+
+    #[test]
+    fn it_arranges_by_duration() {
+        let (positions, _) = arrange(tiles(), Strategy::Duration).unwrap();
+        let ids: Vec<&str> = positions.iter().map(|pos| pos.id()).collect();
+        assert_eq!(ids, ["2", "4", "3", "1"]);
+    }
+
+    #[test]
+    fn it_arranges_portrait_first() {
+        // Sharing a height, so portrait and landscape clips fit one row
+        let tiles = vec![
+            tile("1", "clips/10.mov", 5.0, 1920, 1080),
+            tile("2", "clips/2.mov", 30.0, 608, 1080),
+            tile("3", "clips/1.mov", 10.0, 1440, 1080),
+            tile("4", "clips/3.mov", 20.0, 608, 1080),
+        ];
+        let (positions, stack) = arrange(tiles.clone(), Strategy::Portrait).unwrap();
+
+        assert_eq!(
+            positions,
+            [
+                Position::new("2", 0, 0),
+                Position::new("4", 1, 0),
+                Position::new("3", 2, 0),
+                Position::new("1", 3, 0),
+            ]
+        );
+        assert_eq!(stack, Stack::Horizontal);
+        assert_stackable(&tiles, &positions, stack);
+
+        let mixed = vec![
+            tile("1", "clips/1.mov", 5.0, 1920, 1080),
+            tile("2", "clips/2.mov", 5.0, 1080, 1920),
+        ];
+        assert!(arrange(mixed, Strategy::Portrait).is_err());
+    }
+}
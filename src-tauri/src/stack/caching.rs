@@ -0,0 +1,274 @@
+use super::probing::Probed;
+
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/// Identity of a file on disk, changes whenever the file is replaced or modified
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileKey {
+    path: PathBuf,
+    size: u64,
+    modified: u64,
+}
+
+impl FileKey {
+    pub fn new(path: &str) -> io::Result<FileKey> {
+        let path = fs::canonicalize(path)?;
+        let metadata = fs::metadata(&path)?;
+
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Ok(FileKey {
+            path,
+            size: metadata.len(),
+            modified,
+        })
+    }
+
+    /// Whether the file still exists unchanged
+    fn is_current(&self) -> bool {
+        FileKey::new(&self.path.to_string_lossy()).is_ok_and(|key| key == *self)
+    }
+
+    /// Short hex digest, used to name files derived from this one. FNV-1a over the
+    /// serialized key, so names stay the same across Rust releases and restarts.
+    pub fn digest(&self) -> String {
+        let bytes = serde_json::to_vec(self).unwrap_or_default();
+        let hash = bytes.iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+        format!("{:016x}", hash)
+    }
+}
+
+// Bump whenever what `Probed` holds for a file changes, e.g. how audio-only inputs are
+// recorded, so results of older releases are dropped on load instead of served
+const STORE_VERSION: u32 = 1;
+
+/// Layout of the store on disk
+#[derive(Serialize, Deserialize)]
+struct Store {
+    version: u32,
+    entries: Vec<(FileKey, Probed)>,
+}
+
+/// Probe results shared between commands, persisted to disk once the user opts in
+#[derive(Default)]
+pub struct ProbeCache {
+    entries: Mutex<HashMap<FileKey, Probed>>,
+    store: Mutex<Option<PathBuf>>,
+    // Set by misses until the next flush writes them out
+    dirty: AtomicBool,
+    // Keeps flushes in order, never held while probing
+    writing: Mutex<()>,
+}
+
+impl ProbeCache {
+    pub fn new() -> ProbeCache {
+        ProbeCache::default()
+    }
+
+    // Entries of deleted or changed files, or written by another store version, are left behind
+    fn load(store: &Path) -> HashMap<FileKey, Probed> {
+        let entries: Vec<(FileKey, Probed)> = fs::read(store)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Store>(&bytes).ok())
+            .filter(|loaded| loaded.version == STORE_VERSION)
+            .map(|loaded| loaded.entries)
+            .unwrap_or_default();
+
+        let total = entries.len();
+        let entries: HashMap<FileKey, Probed> = entries
+            .into_iter()
+            .filter(|(key, _)| key.is_current())
+            .collect();
+
+        debug!(
+            "Loaded {} cached probes from {:?}, pruned {}",
+            entries.len(),
+            store,
+            total - entries.len()
+        );
+        entries
+    }
+
+    /// Persist to `store` from now on, merging what it already holds, or stop persisting
+    /// and remove the previous store when `None`. Returns the number of cached probes.
+    pub fn persist_to(&self, store: Option<PathBuf>) -> io::Result<usize> {
+        let previous = std::mem::replace(&mut *self.store.lock().unwrap(), store.clone());
+
+        let Some(store) = store else {
+            if let Some(previous) = previous {
+                let _writing = self.writing.lock().unwrap();
+                match fs::remove_file(previous) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+            }
+            return Ok(self.entries.lock().unwrap().len());
+        };
+
+        let loaded = ProbeCache::load(&store);
+        let count = {
+            let mut entries = self.entries.lock().unwrap();
+            for (key, probed) in loaded {
+                entries.entry(key).or_insert(probed);
+            }
+            entries.len()
+        };
+
+        // Rewrite right away so pruned entries are gone from disk too
+        self.dirty.store(true, Ordering::SeqCst);
+        self.flush();
+        Ok(count)
+    }
+
+    /// Write the entries to the store when persisting and anything changed since the last flush
+    pub fn flush(&self) {
+        let Some(store) = self.store.lock().unwrap().clone() else {
+            return;
+        };
+
+        let _writing = self.writing.lock().unwrap();
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+
+        // Snapshot so the entries aren't locked for the write
+        let entries: Vec<(FileKey, Probed)> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, probed)| (key.clone(), probed.clone()))
+            .collect();
+
+        let written = serde_json::to_vec(&Store {
+            version: STORE_VERSION,
+            entries,
+        })
+        .map_err(io::Error::from)
+        .and_then(|bytes| {
+            if let Some(parent) = store.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&store, bytes)
+        });
+
+        if let Err(e) = written {
+            error!("Failed persisting probe cache: {e}");
+        }
+    }
+
+    /// Return the cached probe for the file or run `probe` and remember its result.
+    /// New results only reach the store on the next `flush`.
+    pub fn fetch<F, E>(&self, path: &str, probe: F) -> Result<Probed, E>
+    where
+        F: FnOnce() -> Result<Probed, E>,
+    {
+        // Files we can't identify are probed but never cached
+        let Ok(key) = FileKey::new(path) else {
+            return probe();
+        };
+
+        if let Some(probed) = self.entries.lock().unwrap().get(&key) {
+            debug!("Probe cache hit: {}", path);
+            return Ok(probed.clone());
+        }
+
+        let probed = probe()?;
+
+        let mut entries = self.entries.lock().unwrap();
+        // An older identity of the same file can't be hit again
+        entries.retain(|cached, _| cached.path != key.path);
+        entries.insert(key, probed.clone());
+        self.dirty.store(true, Ordering::SeqCst);
+
+        Ok(probed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::TempDir;
+    use super::*;
+
+    #[test]
+    fn it_caches_by_identity() {
+        let dir = TempDir::new("caching");
+
+        let input = dir.join("1.mov");
+        let input = input.to_str().unwrap();
+        fs::write(input, b"frames").unwrap();
+
+        let store = dir.join("probes.json");
+        let cache = ProbeCache::new();
+        assert_eq!(cache.persist_to(Some(store.clone())).unwrap(), 0);
+        let probed = Probed {
+            width: 640,
+            ..Default::default()
+        };
+
+        let result: Result<Probed, ()> = cache.fetch(input, || Ok(probed.clone()));
+        assert_eq!(result.unwrap().width, 640);
+        cache.flush();
+
+        // Hit served without probing again, also after reloading from disk
+        let result: Result<Probed, ()> = cache.fetch(input, || Err(()));
+        assert!(result.is_ok());
+
+        let reloaded = ProbeCache::new();
+        assert_eq!(reloaded.persist_to(Some(store.clone())).unwrap(), 1);
+        let result: Result<Probed, ()> = reloaded.fetch(input, || Err(()));
+        assert_eq!(result.unwrap().width, 640);
+
+        // Changed size gives a new identity and prunes the stored one
+        fs::write(input, b"more frames").unwrap();
+        let result: Result<Probed, ()> = cache.fetch(input, || Err(()));
+        assert!(result.is_err());
+        assert_eq!(
+            ProbeCache::new().persist_to(Some(store.clone())).unwrap(),
+            0
+        );
+
+        // Stores written by another version are discarded
+        let result: Result<Probed, ()> = cache.fetch(input, || Ok(probed.clone()));
+        assert!(result.is_ok());
+        cache.flush();
+        let mut stale: serde_json::Value =
+            serde_json::from_slice(&fs::read(&store).unwrap()).unwrap();
+        assert_eq!(stale["entries"].as_array().unwrap().len(), 1);
+        stale["version"] = (STORE_VERSION + 1).into();
+        fs::write(&store, stale.to_string()).unwrap();
+        assert_eq!(
+            ProbeCache::new().persist_to(Some(store.clone())).unwrap(),
+            0
+        );
+
+        // Opting out removes the store
+        reloaded.persist_to(None).unwrap();
+        assert!(!store.exists());
+    }
+
+    #[test]
+    fn it_digests_stably() {
+        let key = FileKey {
+            path: PathBuf::from("/videos/1.mov"),
+            size: 1024,
+            modified: 7,
+        };
+        assert_eq!(key.digest(), "63f23161b6b22d33");
+    }
+}
@@ -0,0 +1,190 @@
+use super::filtering::Filter;
+
+use core::fmt;
+use serde::Deserialize;
+
+// Ranges accepted by the eq filter
+const BRIGHTNESS: (f64, f64) = (-1.0, 1.0);
+const CONTRAST: (f64, f64) = (-1000.0, 1000.0);
+const SATURATION: (f64, f64) = (0.0, 3.0);
+
+/// Clockwise quarter turns a tile can be rotated by
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize)]
+#[serde(try_from = "u16")]
+pub enum Rotation {
+    Quarter,
+    Half,
+    ThreeQuarter,
+}
+
+impl TryFrom<u16> for Rotation {
+    type Error = String;
+
+    fn try_from(degrees: u16) -> Result<Rotation, String> {
+        match degrees {
+            90 => Ok(Rotation::Quarter),
+            180 => Ok(Rotation::Half),
+            270 => Ok(Rotation::ThreeQuarter),
+            _ => Err(format!("Unsupported rotation of {} degrees", degrees)),
+        }
+    }
+}
+
+/// Correction applied to a single tile, in the order given by the GUI
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize)]
+#[serde(tag = "preset", rename_all = "lowercase")]
+pub enum Preset {
+    Hflip,
+    Vflip,
+    Rotate {
+        degrees: Rotation,
+    },
+    Eq {
+        brightness: Option<f64>,
+        contrast: Option<f64>,
+        saturation: Option<f64>,
+    },
+    Denoise {
+        strength: Option<f64>,
+    },
+    Deshake,
+    Grayscale,
+}
+
+impl Preset {
+    pub fn filters(&self) -> Vec<Filter> {
+        match self {
+            Preset::Hflip => vec![Filter::new("hflip")],
+            Preset::Vflip => vec![Filter::new("vflip")],
+            Preset::Rotate { degrees } => match degrees {
+                Rotation::Quarter => vec![Filter::new("transpose").option("dir", "clock")],
+                Rotation::Half => vec![Filter::new("hflip"), Filter::new("vflip")],
+                Rotation::ThreeQuarter => vec![Filter::new("transpose").option("dir", "cclock")],
+            },
+            Preset::Eq {
+                brightness,
+                contrast,
+                saturation,
+            } => {
+                let options = [
+                    ("brightness", brightness),
+                    ("contrast", contrast),
+                    ("saturation", saturation),
+                ];
+
+                let eq = options
+                    .into_iter()
+                    .filter_map(|(key, value)| value.map(|value| (key, value)))
+                    .fold(Filter::new("eq"), |eq, (key, value)| eq.option(key, value));
+
+                vec![eq]
+            }
+            Preset::Denoise { strength } => {
+                let hqdn3d = Filter::new("hqdn3d");
+                vec![match strength {
+                    Some(strength) => hqdn3d.option("luma_spatial", strength),
+                    None => hqdn3d,
+                }]
+            }
+            Preset::Deshake => vec![Filter::new("deshake")],
+            // Desaturate rather than change pixel format so tiles still stack together
+            Preset::Grayscale => vec![Filter::new("hue").option("s", 0)],
+        }
+    }
+
+    /// Width and height of the tile are swapped by this preset
+    pub fn is_transposing(&self) -> bool {
+        matches!(
+            self,
+            Preset::Rotate {
+                degrees: Rotation::Quarter | Rotation::ThreeQuarter
+            }
+        )
+    }
+
+    /// Check values the GUI sent fall within what FFmpeg accepts
+    pub fn validate(&self) -> Result<(), String> {
+        let check = |name: &str, value: Option<f64>, (min, max): (f64, f64)| match value {
+            Some(value) if !(min..=max).contains(&value) => Err(format!(
+                "{} of {} is outside {} to {}",
+                name, value, min, max
+            )),
+            _ => Ok(()),
+        };
+
+        match *self {
+            Preset::Eq {
+                brightness,
+                contrast,
+                saturation,
+            } => {
+                check("Brightness", brightness, BRIGHTNESS)?;
+                check("Contrast", contrast, CONTRAST)?;
+                check("Saturation", saturation, SATURATION)
+            }
+            Preset::Denoise { strength } => check("Denoise strength", strength, (0.0, 255.0)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let filters = self
+            .filters()
+            .iter()
+            .map(Filter::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        write!(f, "{}", filters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_filters() {
+        let presets: Vec<Preset> = serde_json::from_str(
+            r#"[
+                {"preset": "rotate", "degrees": 90},
+                {"preset": "eq", "brightness": 0.1, "saturation": 1.5},
+                {"preset": "grayscale"}
+            ]"#,
+        )
+        .unwrap();
+
+        let result: Vec<String> = presets.iter().map(Preset::to_string).collect();
+        assert_eq!(
+            result,
+            [
+                "transpose=dir=clock",
+                "eq=brightness=0.1:saturation=1.5",
+                "hue=s=0"
+            ]
+        );
+        assert!(presets[0].is_transposing());
+
+        let rotation: Result<Preset, _> =
+            serde_json::from_str(r#"{"preset": "rotate", "degrees": 45}"#);
+        assert!(rotation.is_err());
+    }
+
+    #[test]
+    fn it_validates_ranges() {
+        let eq = Preset::Eq {
+            brightness: Some(2.0),
+            contrast: None,
+            saturation: None,
+        };
+
+        assert!(eq.validate().is_err());
+        assert!(Preset::Denoise {
+            strength: Some(4.0)
+        }
+        .validate()
+        .is_ok());
+    }
+}
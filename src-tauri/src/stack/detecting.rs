@@ -0,0 +1,272 @@
+use super::filtering::{Chain, Filter};
+use super::{Execution, Handle};
+
+use serde::Serialize;
+use std::process::Command;
+
+/// Default scene change score above which a frame counts as a cut
+pub const SCENE_THRESHOLD: f64 = 0.4;
+
+/// Default level in dB below which audio counts as silent
+pub const SILENCE_NOISE: f64 = -30.0;
+
+/// Default shortest stretch of quiet audio reported as silence, in seconds
+pub const SILENCE_DURATION: f64 = 0.5;
+
+/// Shot boundary found by scene detection
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Cut {
+    pub timestamp: f64,
+    pub score: f64,
+}
+
+// Value following `key` in a log line, with or without a space between them
+fn value_after<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let (_, rest) = line.split_once(key)?;
+    rest.split_whitespace().next()
+}
+
+/// Parse `metadata=print` output into cuts
+pub fn parse_scenes(stdout: &str) -> Vec<Cut> {
+    let mut timestamp = None;
+
+    stdout
+        .lines()
+        .filter_map(|line| {
+            if let Some(pts) = value_after(line, "pts_time:") {
+                timestamp = pts.parse().ok();
+                return None;
+            }
+
+            let score = line
+                .trim()
+                .strip_prefix("lavfi.scene_score=")?
+                .parse()
+                .ok()?;
+            Some(Cut {
+                timestamp: timestamp?,
+                score,
+            })
+        })
+        .collect()
+}
+
+/// Silent interval found by silence detection
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Silence {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Parse `silencedetect` log output, silence still open at the end runs until `duration`
+pub fn parse_silences(stderr: &str, duration: f64) -> Vec<Silence> {
+    let mut start = None;
+    let mut silences = Vec::new();
+
+    for line in stderr.lines() {
+        if let Some(value) = value_after(line, "silence_start:") {
+            start = value.parse::<f64>().ok().map(|s| s.max(0.0));
+        }
+
+        if let Some(end) = value_after(line, "silence_end:").and_then(|v| v.parse().ok()) {
+            if let Some(start) = start.take() {
+                silences.push(Silence { start, end });
+            }
+        }
+    }
+
+    if let Some(start) = start {
+        silences.push(Silence {
+            start,
+            end: duration,
+        });
+    }
+
+    silences
+}
+
+/// Trim range covering the first to last non-silent moment, padded and in whole seconds
+/// so it maps directly onto the slider values
+pub fn suggest_trim(silences: &[Silence], duration: f64, padding: f64) -> [f64; 2] {
+    // Tolerance for silence that begins or ends right at the boundaries
+    const EDGE: f64 = 0.05;
+
+    let start = silences
+        .first()
+        .filter(|silence| silence.start <= EDGE)
+        .map(|silence| silence.end)
+        .unwrap_or(0.0);
+
+    let end = silences
+        .last()
+        .filter(|silence| silence.end >= duration - EDGE)
+        .map(|silence| silence.start)
+        .unwrap_or(duration);
+
+    // Entirely silent input, nothing sensible to suggest
+    if start >= end {
+        return [0.0, duration.ceil()];
+    }
+
+    [
+        (start - padding).max(0.0).floor(),
+        (end + padding).min(duration).ceil(),
+    ]
+}
+
+/// Wrapper for running FFmpeg scene detection over the video of an input
+pub struct SceneDetect {
+    input: String,
+    threshold: f64,
+    ffmpeg: Command,
+}
+
+impl SceneDetect {
+    pub fn new(input: &str, threshold: f64) -> SceneDetect {
+        SceneDetect {
+            input: input.into(),
+            threshold,
+            ffmpeg: Command::new("ffmpeg"),
+        }
+    }
+}
+
+impl Execution for SceneDetect {
+    // Frame metadata is printed to stdout
+    const HANDLE: Handle = Handle::Out;
+
+    fn assemble(&mut self) -> &mut Command {
+        let chain = Chain::new()
+            .filter(Filter::new("select").option("expr", format!("gt(scene,{})", self.threshold)))
+            .filter(
+                Filter::new("metadata")
+                    .option("mode", "print")
+                    .option("file", "-"),
+            );
+
+        self.ffmpeg
+            .args(["-v", "error"])
+            .args(["-i", &self.input])
+            .arg("-an")
+            .args(["-vf", &chain.to_string()])
+            .args(["-f", "null"])
+            .arg("-")
+    }
+}
+
+/// Wrapper for running FFmpeg silence detection over the audio of an input
+pub struct SilenceDetect {
+    input: String,
+    noise: f64,
+    duration: f64,
+    ffmpeg: Command,
+}
+
+impl SilenceDetect {
+    pub fn new(input: &str, noise: f64, duration: f64) -> SilenceDetect {
+        SilenceDetect {
+            input: input.into(),
+            noise,
+            duration,
+            ffmpeg: Command::new("ffmpeg"),
+        }
+    }
+}
+
+impl Execution for SilenceDetect {
+    // silencedetect logs its findings to stderr
+    const HANDLE: Handle = Handle::Err;
+
+    fn assemble(&mut self) -> &mut Command {
+        let chain = Chain::new().filter(
+            Filter::new("silencedetect")
+                .option("noise", format!("{}dB", self.noise))
+                .option("d", self.duration),
+        );
+
+        self.ffmpeg
+            .arg("-nostats")
+            .args(["-i", &self.input])
+            .arg("-vn")
+            .args(["-af", &chain.to_string()])
+            .args(["-f", "null"])
+            .arg("-")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn it_parses_scenes() {
+        let stdout = "frame:0    pts:48048   pts_time:4.004\n\
+                      lavfi.scene_score=0.512\n\
+                      frame:1    pts:120120  pts_time:10.01\n\
+                      lavfi.scene_score=0.9\n";
+
+        assert_eq!(
+            parse_scenes(stdout),
+            [
+                Cut {
+                    timestamp: 4.004,
+                    score: 0.512
+                },
+                Cut {
+                    timestamp: 10.01,
+                    score: 0.9
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_silences() {
+        let stderr = "[silencedetect @ 0x1] silence_start: -0.01\n\
+                      [silencedetect @ 0x1] silence_end: 3.2 | silence_duration: 3.21\n\
+                      size=N/A time=00:00:30.00 bitrate=N/A\n\
+                      [silencedetect @ 0x1] silence_start: 27.5\n";
+
+        let silences = parse_silences(stderr, 30.0);
+        assert_eq!(
+            silences,
+            [
+                Silence {
+                    start: 0.0,
+                    end: 3.2
+                },
+                Silence {
+                    start: 27.5,
+                    end: 30.0
+                }
+            ]
+        );
+
+        assert_eq!(suggest_trim(&silences, 30.0, 0.5), [2.0, 28.0]);
+        assert_eq!(suggest_trim(&silences[1..], 30.0, 0.0), [0.0, 28.0]);
+        assert_eq!(suggest_trim(&[], 30.0, 1.0), [0.0, 30.0]);
+    }
+
+    #[test]
+    fn it_assembles_scene_detect() {
+        let mut detect = SceneDetect::new("videos/1.mov", 0.3);
+        let result: Vec<&OsStr> = detect.assemble().get_args().collect();
+
+        assert_eq!(
+            result,
+            [
+                "-v",
+                "error",
+                "-i",
+                "videos/1.mov",
+                "-an",
+                "-vf",
+                r"select=expr=gt(scene\,0.3),metadata=mode=print:file=-",
+                "-f",
+                "null",
+                "-"
+            ]
+        )
+    }
+}
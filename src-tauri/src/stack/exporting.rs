@@ -0,0 +1,76 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Characters that never need quoting in a POSIX shell
+fn is_safe(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_-./:=+,@%".contains(c)
+}
+
+/// Quote a single argument for a POSIX shell
+pub fn quote(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        return arg.into_owned();
+    }
+
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Render program and arguments as a single copy-pasteable shell line
+pub fn render(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Write the command out as an executable `.sh` script
+pub fn write_script(path: &str, command: &Command) -> io::Result<PathBuf> {
+    let mut path = PathBuf::from(path);
+    if path.extension().is_none() {
+        path.set_extension("sh");
+    }
+
+    fs::write(&path, format!("#!/bin/sh\nset -e\n\n{}\n", render(command)))?;
+    make_executable(&path)?;
+
+    Ok(path)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_quotes() {
+        assert_eq!(quote(OsStr::new("videos/1.mov")), "videos/1.mov");
+        assert_eq!(quote(OsStr::new("my clip.mov")), "'my clip.mov'");
+        assert_eq!(quote(OsStr::new("[v]")), "'[v]'");
+        assert_eq!(quote(OsStr::new("it's")), r"'it'\''s'");
+        assert_eq!(quote(OsStr::new("")), "''");
+    }
+
+    #[test]
+    fn it_renders() {
+        let mut command = Command::new("ffmpeg");
+        command.args(["-i", "a b.mov", "-map", "[v]", "out.mkv"]);
+
+        assert_eq!(render(&command), "ffmpeg -i 'a b.mov' -map '[v]' out.mkv");
+    }
+}
@@ -0,0 +1,221 @@
+use core::fmt;
+use log::warn;
+
+// Characters with meaning to the filter option parser
+const OPTION_SPECIAL: [char; 3] = ['\\', '\'', ':'];
+
+// Characters with meaning to the filtergraph parser
+const GRAPH_SPECIAL: [char; 6] = ['\\', '\'', '[', ']', ',', ';'];
+
+fn escape_with(value: &str, special: &[char]) -> String {
+    value.chars().fold(String::new(), |mut output, c| {
+        if special.contains(&c) {
+            output.push('\\');
+        }
+        output.push(c);
+        output
+    })
+}
+
+/// Escape an option value for both levels of FFmpeg filtergraph parsing
+pub fn escape(value: &str) -> String {
+    escape_with(&escape_with(value, &OPTION_SPECIAL), &GRAPH_SPECIAL)
+}
+
+/// Named link between filters, e.g. `[0:v]` or `[v]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pad(String);
+
+impl Pad {
+    pub fn named(name: &str) -> Pad {
+        Pad(name.into())
+    }
+
+    /// Stream of an input file, e.g. `0:v` for the video of the first input
+    pub fn stream(input: usize, kind: &str) -> Pad {
+        Pad(format!("{}:{}", input, kind))
+    }
+}
+
+impl fmt::Display for Pad {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}]", self.0)
+    }
+}
+
+/// Single filter node with keyed options
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    name: String,
+    options: Vec<(String, String)>,
+}
+
+impl Filter {
+    pub fn new(name: &str) -> Filter {
+        Filter {
+            name: name.into(),
+            options: Vec::new(),
+        }
+    }
+
+    pub fn option(mut self, key: &str, value: impl ToString) -> Filter {
+        self.options.push((key.into(), value.to_string()));
+        self
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+
+        for (idx, (key, value)) in self.options.iter().enumerate() {
+            let sep = if idx == 0 { '=' } else { ':' };
+            write!(f, "{}{}={}", sep, key, escape(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Linear sequence of filters between input and output pads
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Chain {
+    inputs: Vec<Pad>,
+    filters: Vec<Filter>,
+    outputs: Vec<Pad>,
+}
+
+impl Chain {
+    pub fn new() -> Chain {
+        Chain::default()
+    }
+
+    pub fn inputs(mut self, pads: Vec<Pad>) -> Chain {
+        self.inputs.extend(pads);
+        self
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Chain {
+        self.filters.push(filter);
+        self
+    }
+
+    pub fn output(mut self, pad: Pad) -> Chain {
+        self.outputs.push(pad);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |pads: &[Pad]| pads.iter().map(Pad::to_string).collect::<String>();
+        let filters = self
+            .filters
+            .iter()
+            .map(Filter::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        write!(
+            f,
+            "{}{}{}",
+            join(&self.inputs),
+            filters,
+            join(&self.outputs)
+        )
+    }
+}
+
+/// Complete graph passed to `-filter_complex`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Graph {
+    chains: Vec<Chain>,
+}
+
+impl Graph {
+    pub fn new() -> Graph {
+        Graph::default()
+    }
+
+    /// Chains without filters are dropped, as FFmpeg rejects them as dangling pads.
+    /// Callers are expected to leave them out, so dropping one is logged.
+    pub fn chain(mut self, chain: Chain) -> Graph {
+        match chain.is_empty() {
+            true => warn!("Dropping chain without filters: {}", chain),
+            false => self.chains.push(chain),
+        }
+        self
+    }
+}
+
+impl fmt::Display for Graph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let chains = self
+            .chains
+            .iter()
+            .map(Chain::to_string)
+            .collect::<Vec<_>>()
+            .join(";");
+
+        write!(f, "{}", chains)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_escapes() {
+        assert_eq!(escape("0_0|w0_0"), "0_0|w0_0");
+        assert_eq!(escape("C:/subs.srt"), r"C\\:/subs.srt");
+        assert_eq!(escape("it's"), r"it\\\'s");
+        assert_eq!(escape("[a],b;"), r"\[a\]\,b\;");
+    }
+
+    #[test]
+    fn it_formats_filter() {
+        let filter = Filter::new("scale")
+            .option("w", 1280)
+            .option("h", -2)
+            .option("flags", "lanczos");
+        assert_eq!(filter.to_string(), "scale=w=1280:h=-2:flags=lanczos");
+        assert_eq!(Filter::new("hflip").to_string(), "hflip");
+    }
+
+    #[test]
+    fn it_formats_graph() {
+        let graph = Graph::new()
+            .chain(
+                Chain::new()
+                    .inputs(vec![Pad::stream(0, "v")])
+                    .filter(Filter::new("hflip"))
+                    .filter(Filter::new("scale").option("w", 640).option("h", 360))
+                    .output(Pad::named("t0")),
+            )
+            .chain(
+                Chain::new()
+                    .inputs(vec![Pad::named("t0"), Pad::stream(1, "v")])
+                    .filter(Filter::new("hstack").option("inputs", 2))
+                    .output(Pad::named("v")),
+            );
+
+        assert_eq!(
+            graph.to_string(),
+            "[0:v]hflip,scale=w=640:h=360[t0];[t0][1:v]hstack=inputs=2[v]"
+        );
+    }
+
+    #[test]
+    fn it_drops_empty_chains() {
+        let graph = Graph::new()
+            .chain(Chain::new().inputs(vec![Pad::stream(1, "v")]))
+            .chain(Chain::new().filter(Filter::new("null")));
+
+        assert_eq!(graph.to_string(), "null");
+    }
+}
@@ -1,7 +1,10 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use super::priming::Primed;
+use super::correcting::Preset;
+use super::priming::{PlaybackMode, Primed, Rect};
+use super::subtitling::Subtitles;
+use super::visualizing::Visualization;
 
 pub trait Identifiable {
     fn id(&self) -> &str;
@@ -25,29 +28,139 @@ impl Identifiable for Source {
     }
 }
 
-#[derive(Default, Deserialize, Clone, Debug)]
+impl Identifiable for Crop {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Identifiable for Speed {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Identifiable for Playback {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Identifiable for Presets {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Identifiable for Visual {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Identifiable for Caption {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+#[derive(Default, Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct Position {
     id: String,
     x: u8,
     y: u8,
 }
 
-#[derive(Default, Deserialize, Clone, Debug)]
+impl Position {
+    pub fn new(id: &str, x: u8, y: u8) -> Position {
+        Position {
+            id: id.into(),
+            x,
+            y,
+        }
+    }
+
+    pub fn x(&self) -> u8 {
+        self.x
+    }
+
+    pub fn y(&self) -> u8 {
+        self.y
+    }
+}
+
+#[derive(Default, Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct Source {
     id: String,
     path: String,
 }
 
+impl Source {
+    pub fn new(id: &str, path: &str) -> Source {
+        Source {
+            id: id.into(),
+            path: path.into(),
+        }
+    }
+}
+
 #[derive(Default, Deserialize, Clone, Debug)]
 pub struct Slider {
     id: String,
     values: [f64; 2],
 }
 
+#[derive(Default, Deserialize, Clone, Debug)]
+pub struct Crop {
+    id: String,
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+#[derive(Default, Deserialize, Clone, Debug)]
+pub struct Speed {
+    id: String,
+    factor: f64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Playback {
+    id: String,
+    #[serde(flatten)]
+    mode: PlaybackMode,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Presets {
+    id: String,
+    presets: Vec<Preset>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Visual {
+    id: String,
+    visualization: Visualization,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Caption {
+    id: String,
+    #[serde(flatten)]
+    subtitles: Subtitles,
+}
+
 pub enum Inputs {
     Position(Position),
     Source(Source),
     Slider(Slider),
+    Crop(Crop),
+    Speed(Speed),
+    Playback(Playback),
+    Presets(Presets),
+    Visual(Visual),
+    Caption(Caption),
 }
 
 impl From<Position> for Inputs {
@@ -68,6 +181,81 @@ impl From<Slider> for Inputs {
     }
 }
 
+impl From<Crop> for Inputs {
+    fn from(crop: Crop) -> Inputs {
+        Inputs::Crop(crop)
+    }
+}
+
+impl From<Speed> for Inputs {
+    fn from(speed: Speed) -> Inputs {
+        Inputs::Speed(speed)
+    }
+}
+
+impl From<Playback> for Inputs {
+    fn from(playback: Playback) -> Inputs {
+        Inputs::Playback(playback)
+    }
+}
+
+impl From<Presets> for Inputs {
+    fn from(presets: Presets) -> Inputs {
+        Inputs::Presets(presets)
+    }
+}
+
+impl From<Visual> for Inputs {
+    fn from(visual: Visual) -> Inputs {
+        Inputs::Visual(visual)
+    }
+}
+
+impl From<Caption> for Inputs {
+    fn from(caption: Caption) -> Inputs {
+        Inputs::Caption(caption)
+    }
+}
+
+/// Per-tile values received from the GUI, matched up by ID when primed
+#[derive(Default, Deserialize, Debug)]
+pub struct Tiles {
+    positions: Vec<Position>,
+    sources: Vec<Source>,
+    #[serde(default)]
+    sliders: Vec<Option<Slider>>,
+    #[serde(default)]
+    crops: Vec<Option<Crop>>,
+    #[serde(default)]
+    speeds: Vec<Option<Speed>>,
+    #[serde(default)]
+    playbacks: Vec<Option<Playback>>,
+    #[serde(default)]
+    presets: Vec<Option<Presets>>,
+    #[serde(default)]
+    visuals: Vec<Option<Visual>>,
+    #[serde(default)]
+    captions: Vec<Option<Caption>>,
+}
+
+impl Tiles {
+    pub fn prime(self) -> Vec<Primed> {
+        // Clean call ensures no empty items on grid.
+        Group::new()
+            .add(self.positions)
+            .add(self.sources)
+            .add_optional(self.sliders)
+            .add_optional(self.crops)
+            .add_optional(self.speeds)
+            .add_optional(self.playbacks)
+            .add_optional(self.presets)
+            .add_optional(self.visuals)
+            .add_optional(self.captions)
+            .clean()
+            .prime()
+    }
+}
+
 /// Group the Position, Slider, and Source values received from the GUI
 pub struct Group {
     group: HashMap<String, Vec<Inputs>>,
@@ -139,6 +327,29 @@ impl Group {
                             primed.start = Some(start as u32);
                             primed.end = Some(end as u32);
                         }
+                        Inputs::Crop(crop) => {
+                            primed.crop = Some(Rect {
+                                x: crop.x,
+                                y: crop.y,
+                                width: crop.width,
+                                height: crop.height,
+                            });
+                        }
+                        Inputs::Speed(speed) => {
+                            primed.speed = Some(speed.factor);
+                        }
+                        Inputs::Playback(playback) => {
+                            primed.playback = Some(playback.mode);
+                        }
+                        Inputs::Presets(presets) => {
+                            primed.presets = presets.presets.clone();
+                        }
+                        Inputs::Visual(visual) => {
+                            primed.visualization = Some(visual.visualization);
+                        }
+                        Inputs::Caption(caption) => {
+                            primed.subtitles = Some(caption.subtitles.clone());
+                        }
                     }
                 }
                 primed
@@ -149,6 +360,7 @@ impl Group {
 
 #[cfg(test)]
 mod tests {
+    use super::super::subtitling::SubtitleSource;
     use super::*;
 
     #[test]
@@ -167,6 +379,28 @@ mod tests {
                 id: String::from("1"),
                 values: [10.0, 20.0],
             })])
+            .add_optional(vec![
+                Some(Crop {
+                    id: String::from("1"),
+                    x: 10,
+                    y: 20,
+                    width: 640,
+                    height: 360,
+                }),
+                None,
+            ])
+            .add_optional(vec![Some(Speed {
+                id: String::from("1"),
+                factor: 0.5,
+            })])
+            .add_optional(vec![Some(Playback {
+                id: String::from("1"),
+                mode: PlaybackMode::Loop { count: 3 },
+            })])
+            .add_optional(vec![Some(Presets {
+                id: String::from("1"),
+                presets: vec![Preset::Hflip, Preset::Deshake],
+            })])
             .clean()
             .prime();
 
@@ -180,8 +414,44 @@ mod tests {
                 y: 1,
                 path: String::from("x.mov"),
                 start: Some(10),
-                end: Some(20)
+                end: Some(20),
+                crop: Some(Rect {
+                    x: 10,
+                    y: 20,
+                    width: 640,
+                    height: 360
+                }),
+                speed: Some(0.5),
+                playback: Some(PlaybackMode::Loop { count: 3 }),
+                presets: vec![Preset::Hflip, Preset::Deshake],
+                visualization: None,
+                subtitles: None
             }
         )
     }
+
+    #[test]
+    fn it_deserializes_playback() {
+        let playback: Playback =
+            serde_json::from_str(r#"{"id": "1", "mode": "loop", "count": 2}"#).unwrap();
+        assert_eq!(playback.mode, PlaybackMode::Loop { count: 2 });
+
+        let playback: Playback =
+            serde_json::from_str(r#"{"id": "1", "mode": "boomerang"}"#).unwrap();
+        assert_eq!(playback.mode, PlaybackMode::Boomerang);
+    }
+
+    #[test]
+    fn it_deserializes_captions() {
+        let caption: Caption = serde_json::from_str(
+            r#"{"id": "1", "source": "embedded", "index": 2, "style": {"size": 28}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            caption.subtitles.source,
+            SubtitleSource::Embedded { index: 2 }
+        );
+        assert_eq!(caption.subtitles.style.size, Some(28));
+    }
 }
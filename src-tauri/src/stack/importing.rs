@@ -0,0 +1,135 @@
+use super::grouping::{Position, Source};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Extensions picked up when no filter is given
+pub const MEDIA_EXTENSIONS: [&str; 19] = [
+    "mp4", "mov", "mkv", "webm", "avi", "m4v", "mts", "m2ts", "mxf", "mpg", "mpeg", "wmv",
+    // Audio-only, stacked as visualizations
+    "mp3", "wav", "flac", "m4a", "aac", "ogg", "opus",
+];
+
+fn is_media(path: &Path, extensions: &[String]) -> bool {
+    let Some(ext) = path.extension().map(|e| e.to_string_lossy().to_lowercase()) else {
+        return false;
+    };
+
+    match extensions.is_empty() {
+        true => MEDIA_EXTENSIONS.contains(&ext.as_str()),
+        false => extensions
+            .iter()
+            .any(|filter| filter.trim_start_matches('.').eq_ignore_ascii_case(&ext)),
+    }
+}
+
+/// Collect media files in a directory, sorted by path
+pub fn scan(directory: &Path, recursive: bool, extensions: &[String]) -> io::Result<Vec<String>> {
+    let mut pending = vec![directory.to_path_buf()];
+    let mut found: Vec<PathBuf> = Vec::new();
+
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            // Symlinked directories aren't followed, one pointing at an ancestor never ends
+            if entry.file_type()?.is_dir() {
+                if recursive {
+                    pending.push(path);
+                }
+            } else if !path.is_dir() && is_media(&path, extensions) {
+                found.push(path);
+            }
+        }
+    }
+
+    found.sort();
+    Ok(found
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+// Rounded up so a few clips still spread into a grid rather than a single column
+fn grid_columns(n: usize) -> usize {
+    ((n as f64).sqrt().ceil() as usize).max(1)
+}
+
+/// Lay the paths out row by row in a near-square grid, never more rows than columns.
+/// Fails when the grid outgrows the positions the GUI can hold.
+pub fn arrange_grid(paths: &[String]) -> Result<Vec<(Position, Source)>, String> {
+    let cols = grid_columns(paths.len());
+
+    paths
+        .iter()
+        .enumerate()
+        .map(|(idx, path)| {
+            let id = (idx + 1).to_string();
+            let (x, y) = match (u8::try_from(idx % cols), u8::try_from(idx / cols)) {
+                (Ok(x), Ok(y)) => (x, y),
+                _ => return Err(format!("Too many files for one grid: {}", paths.len())),
+            };
+
+            Ok((Position::new(&id, x, y), Source::new(&id, path)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::TempDir;
+    use super::*;
+
+    #[test]
+    fn it_scans() {
+        let dir = TempDir::new("importing");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+
+        for name in ["b.MOV", "a.mp4", "notes.txt", "nested/c.mkv"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let names = |paths: Vec<String>| -> Vec<String> {
+            paths
+                .iter()
+                .map(|p| Path::new(p).file_name().unwrap().to_string_lossy().into())
+                .collect()
+        };
+
+        // Would loop forever if followed
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, dir.join("nested/loop")).unwrap();
+
+        let result = scan(&dir, false, &[]).unwrap();
+        assert_eq!(names(result), ["a.mp4", "b.MOV"]);
+
+        let result = scan(&dir, true, &[]).unwrap();
+        assert_eq!(names(result), ["a.mp4", "b.MOV", "c.mkv"]);
+
+        let result = scan(&dir, true, &[String::from(".mkv")]).unwrap();
+        assert_eq!(names(result), ["c.mkv"]);
+    }
+
+    #[test]
+    fn it_arranges_grid() {
+        let paths: Vec<String> = (1..=9).map(|n| format!("{}.mov", n)).collect();
+        let result = arrange_grid(&paths).unwrap();
+
+        assert_eq!(result.len(), 9);
+        assert_eq!(result[4].0, Position::new("5", 1, 1));
+        assert_eq!(
+            result[8],
+            (Position::new("9", 2, 2), Source::new("9", "9.mov"))
+        );
+
+        // Few clips still get a grid rather than a single column
+        let result = arrange_grid(&paths[..3]).unwrap();
+        assert_eq!(result[1].0, Position::new("2", 1, 0));
+        assert_eq!(result[2].0, Position::new("3", 0, 1));
+
+        let paths: Vec<String> = (0..70_000).map(|n| format!("{}.mov", n)).collect();
+        assert!(arrange_grid(&paths).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use super::animating::Animation;
+use super::segmenting::Segmenting;
+use super::streaming::Target;
+use super::subtitling::Subtitles;
+use super::tagging::Metadata;
+use super::watermarking::Watermark;
+
+use chrono::{DateTime, Local};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Template used when the chosen output is a directory
+pub const DEFAULT_TEMPLATE: &str = "stacked-{layout}-{count}-{date}_{time}";
+
+// Upper bound for the increment policy before giving up
+pub const MAX_COUNTER: u32 = 9999;
+
+// Extensions kept when a template ends in one, any other dot is part of the name
+const CONTAINERS: [&str; 16] = [
+    "mkv", "mp4", "mov", "m4v", "webm", "avi", "ts", "flv", "gif", "webp", "png", "apng", "m3u8",
+    "mpd", "jpg", "jpeg",
+];
+
+/// What to do when the resolved output path already exists
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Collision {
+    #[default]
+    Increment,
+    Overwrite,
+    Fail,
+    // Reported back as a conflict so the GUI can confirm before rerunning with Overwrite
+    Ask,
+}
+
+impl Collision {
+    /// FFmpeg flag that stops it from prompting interactively
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Collision::Overwrite => "-y",
+            Collision::Increment | Collision::Fail | Collision::Ask => "-n",
+        }
+    }
+}
+
+/// Output location received from the GUI.
+/// The path is either a directory or a full file path, both may contain placeholders.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct Destination {
+    pub path: String,
+    #[serde(default)]
+    pub collision: Collision,
+    // Animated image instead of a video when set
+    #[serde(default)]
+    pub animation: Option<Animation>,
+    // Playlist and segments for web playback when set, the path names the playlist
+    #[serde(default)]
+    pub segmenting: Option<Segmenting>,
+    // Pushed live to a network endpoint when set, the path is ignored
+    #[serde(default)]
+    pub streaming: Option<Target>,
+    // Burned over the whole mosaic, following the tile the audio comes from
+    #[serde(default)]
+    pub subtitles: Option<Subtitles>,
+    // Laid over the whole mosaic, above any subtitles
+    #[serde(default)]
+    pub watermark: Option<Watermark>,
+    // Tags of video and segmented outputs, animations carry none
+    #[serde(default)]
+    pub metadata: Metadata,
+}
+
+#[derive(Debug)]
+pub enum NamingError {
+    Exists(String),
+    Exhausted(String),
+}
+
+impl fmt::Display for NamingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NamingError::Exists(path) => write!(f, "Output already exists: {}", path),
+            NamingError::Exhausted(path) => {
+                write!(f, "No free output name left for: {}", path)
+            }
+        }
+    }
+}
+
+/// Values substituted into the template placeholders
+pub struct Fields {
+    pub timestamp: DateTime<Local>,
+    pub layout: String,
+    pub count: usize,
+    pub stem: String,
+}
+
+impl Fields {
+    fn render(&self, template: &str, counter: u32) -> String {
+        template
+            .replace("{date}", &self.timestamp.format("%Y-%m-%d").to_string())
+            .replace("{time}", &self.timestamp.format("%H-%M-%S").to_string())
+            .replace("{layout}", &self.layout)
+            .replace("{count}", &self.count.to_string())
+            .replace("{stem}", &self.stem)
+            .replace("{n}", &format!("{:0>3}", counter))
+    }
+}
+
+impl Destination {
+    /// Split into the parent directory and the file name template
+    fn split(&self) -> (PathBuf, String) {
+        let path = PathBuf::from(&self.path);
+
+        if path.is_dir() {
+            return (path, DEFAULT_TEMPLATE.into());
+        }
+
+        let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let template = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or(DEFAULT_TEMPLATE.into());
+
+        (parent, template)
+    }
+
+    fn candidate(&self, fields: &Fields, extension: &str, counter: u32) -> PathBuf {
+        let (parent, template) = self.split();
+        let name = fields.render(&template, counter);
+
+        // Animations and segments need their own muxer, only video keeps the template's container
+        let keeps = self.animation.is_none() && self.segmenting.is_none();
+
+        // A stem like `clip.v2` keeps its dot and still gets the default extension
+        let (stem, extension) = match name.rsplit_once('.') {
+            Some((stem, ext)) if CONTAINERS.contains(&ext.to_lowercase().as_str()) => {
+                let ext = if keeps { ext } else { extension };
+                (stem.to_string(), ext.to_string())
+            }
+            _ => (name, extension.to_string()),
+        };
+
+        // Templates without a counter placeholder get a suffix once taken
+        let stem = match counter > 1 && !template.contains("{n}") {
+            true => format!("{}-{}", stem, counter),
+            false => stem,
+        };
+
+        parent.join(format!("{}.{}", stem, extension))
+    }
+
+    /// Resolve the final output path according to the collision policy
+    pub fn resolve(&self, fields: &Fields, extension: &str) -> Result<PathBuf, NamingError> {
+        let first = self.candidate(fields, extension, 1);
+
+        match self.collision {
+            // Asking is settled by the caller once it knows whether the path is taken
+            Collision::Overwrite | Collision::Ask => Ok(first),
+            Collision::Fail if first.exists() => {
+                Err(NamingError::Exists(first.to_string_lossy().into_owned()))
+            }
+            Collision::Fail => Ok(first),
+            Collision::Increment => (1..=MAX_COUNTER)
+                .map(|counter| self.candidate(fields, extension, counter))
+                .find(|path| !path.exists())
+                .ok_or(NamingError::Exhausted(first.to_string_lossy().into_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::TempDir;
+    use super::*;
+    use chrono::TimeZone;
+
+    fn fields() -> Fields {
+        Fields {
+            timestamp: Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap(),
+            layout: String::from("xstack"),
+            count: 4,
+            stem: String::from("intro"),
+        }
+    }
+
+    #[test]
+    fn it_renders_placeholders() {
+        let result = fields().render("{stem}-{layout}-{count}-{date}_{time}-{n}", 2);
+        assert_eq!(result, "intro-xstack-4-2024-03-09_14-05-07-002")
+    }
+
+    #[test]
+    fn it_resolves_full_path() {
+        let destination = Destination {
+            path: String::from("videos/{stem}-{layout}.mp4"),
+            collision: Collision::Fail,
+            ..Default::default()
+        };
+
+        let result = destination.resolve(&fields(), "mkv").unwrap();
+        assert_eq!(result, PathBuf::from("videos/intro-xstack.mp4"));
+
+        // A resolved path sent back after confirming resolves to itself
+        let confirmed = Destination {
+            path: result.to_string_lossy().into_owned(),
+            collision: Collision::Overwrite,
+            ..Default::default()
+        };
+        assert_eq!(confirmed.resolve(&fields(), "mkv").unwrap(), result);
+
+        let versioned = Fields {
+            stem: String::from("clip.v2"),
+            ..fields()
+        };
+        let destination = Destination {
+            path: String::from("videos/{stem}"),
+            ..destination
+        };
+        let result = destination.resolve(&versioned, "mkv").unwrap();
+        assert_eq!(result, PathBuf::from("videos/clip.v2.mkv"));
+
+        let animated = Destination {
+            path: String::from("videos/review.mkv"),
+            animation: Some(serde_json::from_str(r#"{"format": "gif"}"#).unwrap()),
+            ..destination
+        };
+        let result = animated.resolve(&fields(), "gif").unwrap();
+        assert_eq!(result, PathBuf::from("videos/review.gif"))
+    }
+
+    #[test]
+    fn it_increments_on_collision() {
+        let dir = TempDir::new("naming");
+        std::fs::write(dir.join("intro.mkv"), b"").unwrap();
+
+        let destination = Destination {
+            path: dir.join("{stem}").to_string_lossy().into_owned(),
+            collision: Collision::Increment,
+            ..Default::default()
+        };
+        let result = destination.resolve(&fields(), "mkv").unwrap();
+        assert_eq!(result, dir.join("intro-2.mkv"));
+
+        let destination = Destination {
+            collision: Collision::Fail,
+            ..destination
+        };
+        assert!(destination.resolve(&fields(), "mkv").is_err());
+    }
+}
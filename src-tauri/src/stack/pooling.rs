@@ -0,0 +1,58 @@
+use std::sync::Mutex;
+use std::thread;
+
+// Keeps concurrent FFmpeg/FFprobe processes from swamping the machine
+const MAX_WORKERS: usize = 8;
+
+fn worker_count(jobs: usize) -> usize {
+    let available = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+
+    available.min(MAX_WORKERS).min(jobs).max(1)
+}
+
+/// Run `job` over every item on a bounded pool of worker threads.
+/// Results are returned in completion order.
+pub fn pooled<T, R, F>(items: Vec<T>, job: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let workers = worker_count(items.len());
+    let queue = Mutex::new(items.into_iter());
+    let results = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                // Lock is released before running the job
+                let next = queue.lock().unwrap().next();
+                match next {
+                    Some(item) => {
+                        let result = job(item);
+                        results.lock().unwrap().push(result);
+                    }
+                    None => break,
+                }
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_runs_every_job() {
+        let mut result = pooled((0..20).collect(), |n: u32| n * 2);
+        result.sort();
+
+        assert_eq!(result, (0..20).map(|n| n * 2).collect::<Vec<_>>());
+        assert!(pooled(Vec::<u32>::new(), |n| n).is_empty());
+    }
+}
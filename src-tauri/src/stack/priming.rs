@@ -1,4 +1,13 @@
+use super::correcting::Preset;
+use super::probing::Probed;
+use super::subtitling::Subtitles;
+use super::visualizing::Visualization;
+
 use core::fmt;
+use serde::Deserialize;
+
+/// Frames the loop filter holds at most, every one of them decoded in memory
+pub const LOOP_FRAMES: i32 = i16::MAX as i32;
 
 pub trait Duration {
     fn as_ts(&self) -> String;
@@ -11,8 +20,64 @@ impl Duration for u32 {
     }
 }
 
+/// Region of the source frame kept for a tile
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    /// Non-empty and entirely inside a frame of the given size
+    pub fn fits(&self, width: u16, height: u16) -> bool {
+        self.width > 0
+            && self.height > 0
+            && self.x as u32 + self.width as u32 <= width as u32
+            && self.y as u32 + self.height as u32 <= height as u32
+    }
+}
+
+impl fmt::Display for Rect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}+{}+{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+/// How a tile plays back once trimmed
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum PlaybackMode {
+    Reverse,
+    Loop { count: u16 },
+    // Forward then reverse
+    Boomerang,
+}
+
+impl PlaybackMode {
+    /// Number of times the trimmed segment plays
+    pub fn repeats(&self) -> u16 {
+        match self {
+            PlaybackMode::Reverse => 1,
+            PlaybackMode::Loop { count } => *count,
+            PlaybackMode::Boomerang => 2,
+        }
+    }
+}
+
+impl fmt::Display for PlaybackMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlaybackMode::Reverse => write!(f, "reverse"),
+            PlaybackMode::Loop { count } => write!(f, "loop x{}", count),
+            PlaybackMode::Boomerang => write!(f, "boomerang"),
+        }
+    }
+}
+
 /// The result of grouping GUI input by ID
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, PartialEq, Default, Clone)]
 pub struct Primed {
     pub id: String,
     pub x: u8,
@@ -20,17 +85,89 @@ pub struct Primed {
     pub path: String,
     pub start: Option<u32>,
     pub end: Option<u32>,
+    pub crop: Option<Rect>,
+    pub speed: Option<f64>,
+    pub playback: Option<PlaybackMode>,
+    pub presets: Vec<Preset>,
+    // Only used when the input has no video
+    pub visualization: Option<Visualization>,
+    pub subtitles: Option<Subtitles>,
+}
+
+impl Primed {
+    /// Length of the segment kept by the trim
+    pub fn trimmed_duration(&self, source_duration: f64) -> f64 {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => end.saturating_sub(start) as f64,
+            _ => source_duration,
+        }
+    }
+
+    /// Length of this tile in the output once trimmed, repeated and sped up or slowed down
+    pub fn output_duration(&self, source_duration: f64) -> f64 {
+        let repeats = self.playback.map(|mode| mode.repeats()).unwrap_or(1);
+        self.trimmed_duration(source_duration) * repeats as f64 / self.speed.unwrap_or(1.0)
+    }
+
+    /// Every playback mode holds the decoded frames of the trimmed segment, so the segment must
+    /// stay within `limit` seconds, and loops within the frames the loop filter can hold
+    pub fn check_playback(&self, probed: &Probed, limit: f64) -> Result<(), String> {
+        let Some(mode) = self.playback else {
+            return Ok(());
+        };
+
+        if mode.repeats() == 0 {
+            return Err(format!("Loop of {} must play at least once", self.path));
+        }
+
+        let length = self.trimmed_duration(probed.duration);
+        if length > limit {
+            return Err(format!(
+                "Unable to {} {}: trimmed segment is {:.0}s, buffered playback is limited to {:.0}s. \
+                 Trim the tile shorter or raise the limit.",
+                mode, self.path, length, limit
+            ));
+        }
+
+        // Frame rate is unknown for audio-only tiles, whose loops count samples instead
+        let frames = LOOP_FRAMES as f64 / probed.fps;
+        if matches!(mode, PlaybackMode::Loop { .. }) && probed.fps > 0.0 && length > frames {
+            return Err(format!(
+                "Unable to loop {}: at {:.2} fps only {:.0}s fit in the loop buffer, \
+                 trim the tile shorter",
+                self.path, probed.fps, frames
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 impl fmt::Display for Primed {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let fmt_ts = |dur: Option<u32>| dur.map(|v| v.as_ts()).unwrap_or(String::from("Not set"));
         let (start, end) = (fmt_ts(self.start), fmt_ts(self.end));
+        let crop = self
+            .crop
+            .map(|rect| rect.to_string())
+            .unwrap_or(String::from("Not set"));
+        let speed = self.speed.unwrap_or(1.0);
+        let playback = self
+            .playback
+            .map(|mode| mode.to_string())
+            .unwrap_or(String::from("forward"));
+
+        let presets = self
+            .presets
+            .iter()
+            .map(Preset::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
 
         write!(
             f,
-            "Id: {} | x: {}, y: {} | path: {} | start: {}, end: {}",
-            self.id, self.x, self.y, self.path, start, end
+            "Id: {} | x: {}, y: {} | path: {} | start: {}, end: {} | crop: {} | speed: {}x | playback: {} | presets: {}",
+            self.id, self.x, self.y, self.path, start, end, crop, speed, playback, presets
         )
     }
 }
@@ -50,4 +187,68 @@ mod tests {
         assert_eq!(primed.start.unwrap().as_ts(), "00:39:01");
         assert_eq!(primed.end.unwrap().as_ts(), "01:08:43");
     }
+
+    #[test]
+    fn it_calculates_output_duration() {
+        let primed = Primed {
+            start: Some(10),
+            end: Some(40),
+            speed: Some(2.0),
+            ..Default::default()
+        };
+
+        assert_eq!(primed.output_duration(120.0), 15.0);
+        assert_eq!(Primed::default().output_duration(120.0), 120.0);
+
+        let boomerang = Primed {
+            playback: Some(PlaybackMode::Boomerang),
+            ..primed
+        };
+        assert_eq!(boomerang.trimmed_duration(120.0), 30.0);
+        assert_eq!(boomerang.output_duration(120.0), 30.0);
+    }
+
+    #[test]
+    fn it_checks_playback() {
+        let primed = Primed {
+            path: String::from("clips/1.mov"),
+            playback: Some(PlaybackMode::Loop { count: 3 }),
+            ..Default::default()
+        };
+        let probed = Probed {
+            duration: 20.0,
+            fps: 30.0,
+            ..Default::default()
+        };
+
+        assert!(primed.check_playback(&probed, 30.0).is_ok());
+        assert!(primed.check_playback(&probed, 10.0).is_err());
+
+        // 1200 seconds at 30 fps is more than the loop filter holds
+        let long = Probed {
+            duration: 1200.0,
+            ..probed.clone()
+        };
+        assert!(primed.check_playback(&long, f64::MAX).is_err());
+
+        let none = Primed {
+            playback: Some(PlaybackMode::Loop { count: 0 }),
+            ..primed
+        };
+        assert!(none.check_playback(&probed, 30.0).is_err());
+    }
+
+    #[test]
+    fn it_fits_crop() {
+        let rect = Rect {
+            x: 100,
+            y: 50,
+            width: 1820,
+            height: 1030,
+        };
+
+        assert!(rect.fits(1920, 1080));
+        assert!(!rect.fits(1280, 720));
+        assert!(!Rect::default().fits(1920, 1080));
+    }
 }
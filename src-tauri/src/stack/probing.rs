@@ -1,7 +1,33 @@
+use super::priming::Rect;
 use super::{Execution, Handle};
 
+use core::fmt;
+use log::error;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::process::{Command, Stdio};
+
+// Demuxers FFmpeg uses for single images
+const IMAGE_FORMATS: [&str; 2] = ["image2", "_pipe"];
+
+#[derive(Debug)]
+pub enum ProbeError {
+    // FFprobe couldn't start or read the file, with its own error output
+    Unsupported(String),
+    Unparseable(serde_json::Error),
+    NoVideoStream,
+    NoDuration,
+}
+
+impl fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProbeError::Unsupported(reason) => write!(f, "Unsupported file: {}", reason),
+            ProbeError::Unparseable(e) => write!(f, "Unable to parse FFprobe output: {}", e),
+            ProbeError::NoVideoStream => write!(f, "No video or audio stream found"),
+            ProbeError::NoDuration => write!(f, "Unable to determine duration"),
+        }
+    }
+}
 
 #[derive(Deserialize)]
 struct ProbeOutput {
@@ -9,16 +35,64 @@ struct ProbeOutput {
     format: Format,
 }
 
+#[derive(Deserialize, Default)]
+struct Disposition {
+    #[serde(default)]
+    attached_pic: u8,
+}
+
 #[derive(Deserialize)]
 struct Stream {
+    codec_type: String,
+    #[serde(default)]
     width: u16,
+    #[serde(default)]
     height: u16,
+    #[serde(default)]
+    channels: u16,
+    // Fraction like `30000/1001`, `0/0` when unknown
+    #[serde(default)]
+    avg_frame_rate: String,
     duration: Option<String>,
+    #[serde(default)]
+    disposition: Disposition,
+}
+
+impl Stream {
+    // Cover art embedded in audio files shows up as a single frame video stream
+    fn is_video(&self) -> bool {
+        self.codec_type == "video" && self.disposition.attached_pic == 0
+    }
 }
+
 #[derive(Deserialize)]
 struct Format {
     filename: String,
-    duration: String,
+    #[serde(default)]
+    format_name: String,
+    duration: Option<String>,
+}
+
+impl Format {
+    fn is_image(&self) -> bool {
+        IMAGE_FORMATS
+            .iter()
+            .any(|image| self.format_name.ends_with(image))
+    }
+}
+
+// Frame rates are reported as fractions
+fn parse_rate(value: &str) -> Option<f64> {
+    let (num, den) = value.split_once('/')?;
+    let rate = num.parse::<f64>().ok()? / den.parse::<f64>().ok()?;
+    rate.is_finite().then_some(rate)
+}
+
+// FFprobe reports `N/A` for unknown values
+fn parse_duration(value: Option<&String>) -> Option<f64> {
+    value
+        .and_then(|value| value.parse::<f64>().ok())
+        .filter(|duration| duration.is_finite() && *duration > 0.0)
 }
 
 /// Represent output of executing FFprobe
@@ -28,31 +102,78 @@ pub struct Probed {
     pub duration: f64,
     pub height: u16,
     pub width: u16,
+    #[serde(default)]
+    pub audio: bool,
+    // Of the first audio stream, which decides where the stack's audio comes from
+    #[serde(default)]
+    pub channels: u16,
+    // Zero when unknown
+    #[serde(default)]
+    pub fps: f64,
+    // Rendered as a visualization, with dimensions assigned when stacked
+    #[serde(default)]
+    pub audio_only: bool,
 }
 
 impl Probed {
-    pub fn build(stdout: &[u8]) -> Result<Probed, serde_json::Error> {
-        let output: ProbeOutput = serde_json::from_slice(stdout)?;
-
-        match output.streams.first() {
-            Some(stream) => {
-                // Output of FFprobe should always give us some float string duration
-                let duration = &stream
-                    .duration
-                    .as_ref()
-                    .unwrap_or(&output.format.duration)
-                    .parse::<f64>()
-                    .unwrap();
-
-                Ok(Probed {
-                    filename: output.format.filename,
-                    duration: *duration,
-                    height: stream.height,
-                    width: stream.width,
-                })
-            }
-            None => Ok(Probed::default()),
+    pub fn build(stdout: &[u8]) -> Result<Probed, ProbeError> {
+        let output: ProbeOutput =
+            serde_json::from_slice(stdout).map_err(ProbeError::Unparseable)?;
+
+        if output.format.is_image() {
+            return Err(ProbeError::Unsupported(String::from(
+                "still images can't be stacked",
+            )));
         }
+
+        let audio = output.streams.iter().find(|s| s.codec_type == "audio");
+        let video = output.streams.iter().find(|s| s.is_video());
+
+        // Without video the audio stream stands in, sized later to match the other tiles
+        let stream = video.or(audio).ok_or(ProbeError::NoVideoStream)?;
+        let duration = parse_duration(stream.duration.as_ref())
+            .or_else(|| parse_duration(output.format.duration.as_ref()))
+            .ok_or(ProbeError::NoDuration)?;
+
+        Ok(Probed {
+            filename: output.format.filename,
+            duration,
+            height: stream.height,
+            width: stream.width,
+            audio: audio.is_some(),
+            channels: audio.map(|stream| stream.channels).unwrap_or_default(),
+            fps: video
+                .and_then(|stream| parse_rate(&stream.avg_frame_rate))
+                .unwrap_or_default(),
+            audio_only: video.is_none(),
+        })
+    }
+
+    /// Dimensions given to an audio-only tile
+    pub fn sized(&self, width: u16, height: u16) -> Probed {
+        Probed {
+            width,
+            height,
+            ..self.clone()
+        }
+    }
+
+    /// Dimensions after a quarter turn
+    pub fn transposed(&self) -> Probed {
+        Probed {
+            width: self.height,
+            height: self.width,
+            ..self.clone()
+        }
+    }
+
+    /// Dimensions after cropping, `None` when the crop doesn't fit the frame
+    pub fn cropped(&self, rect: &Rect) -> Option<Probed> {
+        rect.fits(self.width, self.height).then(|| Probed {
+            width: rect.width,
+            height: rect.height,
+            ..self.clone()
+        })
     }
 }
 
@@ -69,6 +190,27 @@ impl Probe {
             ffprobe: Command::new("ffprobe"),
         }
     }
+
+    /// Probe the input, keeping FFprobe's error output to explain failures
+    pub fn run(&mut self) -> Result<Probed, ProbeError> {
+        let output = self
+            .assemble()
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| {
+                error!("Spawning FFprobe failed: {e}");
+                ProbeError::Unsupported(format!("unable to run FFprobe: {}", e))
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.trim().lines().last().unwrap_or("FFprobe failed");
+            return Err(ProbeError::Unsupported(reason.into()));
+        }
+
+        Probed::build(&output.stdout)
+    }
 }
 
 impl Execution for Probe {
@@ -77,11 +219,10 @@ impl Execution for Probe {
 
     fn assemble(&mut self) -> &mut Command {
         self.ffprobe
-            .args(["-v", "quiet"])
+            .args(["-v", "error"])
             .args(["-print_format", "json"])
             .arg("-show_format")
             .arg("-show_streams")
-            .args(["-select_streams", "v:0"])
             .arg(&self.input)
     }
 }
@@ -92,7 +233,7 @@ pub trait ProbedDimensions {
     fn is_same_dimensions(&self) -> bool;
 }
 
-impl ProbedDimensions for Vec<Probed> {
+impl ProbedDimensions for [Probed] {
     fn is_same_width(&self) -> bool {
         self.windows(2).all(|p| p[0].width == p[1].width)
     }
@@ -121,18 +262,70 @@ mod tests {
             result,
             [
                 "-v",
-                "quiet",
+                "error",
                 "-print_format",
                 "json",
                 "-show_format",
                 "-show_streams",
-                "-select_streams",
-                "v:0",
                 "videos/1.mov"
             ]
         )
     }
 
+    #[test]
+    fn it_builds() {
+        let stdout = br#"{
+            "streams": [
+                {"codec_type": "audio", "channels": 2, "duration": "12.0"},
+                {"codec_type": "video", "width": 1920, "height": 1080, "avg_frame_rate": "30000/1001"}
+            ],
+            "format": {"filename": "videos/1.mov", "duration": "12.5"}
+        }"#;
+
+        let probed = Probed::build(stdout).unwrap();
+        assert_eq!((probed.width, probed.height), (1920, 1080));
+        assert_eq!(probed.duration, 12.5);
+        assert!(probed.audio);
+        assert_eq!(probed.channels, 2);
+        assert_eq!(format!("{:.3}", probed.fps), "29.970");
+        assert!(!probed.audio_only);
+
+        let stdout = br#"{
+            "streams": [{"codec_type": "audio", "duration": "1800.0"}],
+            "format": {"filename": "podcast.mp3", "duration": "1800.1"}
+        }"#;
+
+        let probed = Probed::build(stdout).unwrap();
+        assert_eq!(probed.duration, 1800.0);
+        assert!(probed.audio_only);
+    }
+
+    #[test]
+    fn it_reports_errors() {
+        let build = |stdout: &str| Probed::build(stdout.as_bytes()).unwrap_err();
+
+        assert!(matches!(build("not json"), ProbeError::Unparseable(_)));
+
+        let result = build(r#"{"streams": [], "format": {"filename": "notes.txt"}}"#);
+        assert!(matches!(result, ProbeError::NoVideoStream));
+
+        let result = build(
+            r#"{
+                "streams": [{"codec_type": "video", "width": 640, "height": 360, "duration": "N/A"}],
+                "format": {"filename": "live.ts", "format_name": "mpegts"}
+            }"#,
+        );
+        assert!(matches!(result, ProbeError::NoDuration));
+
+        let result = build(
+            r#"{
+                "streams": [{"codec_type": "video", "width": 640, "height": 360}],
+                "format": {"filename": "still.png", "format_name": "png_pipe"}
+            }"#,
+        );
+        assert!(matches!(result, ProbeError::Unsupported(_)));
+    }
+
     #[test]
     fn it_is_same_dimensions() {
         let probed: Vec<Probed> = (1..3)
@@ -150,7 +343,7 @@ mod tests {
 
     #[test]
     fn it_is_diff_dimensions() {
-        let probed = vec![
+        let probed = [
             Probed {
                 height: 50,
                 width: 75,
@@ -0,0 +1,296 @@
+use super::correcting::Preset;
+use super::naming::{Collision, MAX_COUNTER};
+use super::priming::Primed;
+use super::probing::Probed;
+use super::stacking::Stack;
+use super::subtitling::Subtitles;
+use super::visualizing::Visualization;
+
+use chrono::Local;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// Input file with what FFprobe reported about it
+#[derive(Serialize, Debug)]
+pub struct Input {
+    pub path: String,
+    pub probe: Probed,
+}
+
+/// Primed tile as placed on the grid, with filter modifiers in their display form
+#[derive(Serialize, Debug)]
+pub struct Tile {
+    pub path: String,
+    pub x: u8,
+    pub y: u8,
+    pub start: Option<u32>,
+    pub end: Option<u32>,
+    pub crop: Option<String>,
+    pub speed: Option<f64>,
+    pub playback: Option<String>,
+    pub presets: Vec<String>,
+    pub subtitles: Option<Subtitles>,
+    pub visualization: Option<Visualization>,
+}
+
+impl From<&Primed> for Tile {
+    fn from(prime: &Primed) -> Tile {
+        Tile {
+            path: prime.path.clone(),
+            x: prime.x,
+            y: prime.y,
+            start: prime.start,
+            end: prime.end,
+            crop: prime.crop.map(|rect| rect.to_string()),
+            speed: prime.speed,
+            playback: prime.playback.map(|mode| mode.to_string()),
+            presets: prime.presets.iter().map(Preset::to_string).collect(),
+            subtitles: prime.subtitles.clone(),
+            visualization: prime.visualization,
+        }
+    }
+}
+
+/// Record of a finished render, written next to its output
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub created: String,
+    pub ffmpeg_version: Option<String>,
+    pub stack: Stack,
+    pub inputs: Vec<Input>,
+    pub tiles: Vec<Tile>,
+    pub args: Vec<String>,
+    pub output: String,
+    // Playlists count together with the segments and variant playlists named after them
+    pub output_size: Option<u64>,
+    // Wall-clock seconds FFmpeg ran for
+    pub elapsed: f64,
+    // Not set when FFmpeg was killed by a signal
+    pub exit_code: Option<i32>,
+}
+
+/// Bytes written for `output`, which spread over every file named after a playlist
+fn output_size(output: &str) -> Option<u64> {
+    let path = Path::new(output);
+    let size = fs::metadata(path).ok()?.len();
+
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    if !matches!(extension, Some("m3u8" | "mpd")) {
+        return Some(size);
+    }
+
+    // Segments are written next to the playlist as `{stem}_...`
+    let prefix = format!("{}_", path.file_stem()?.to_string_lossy());
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let segments: u64 = fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+        .sum();
+
+    Some(size + segments)
+}
+
+/// First line of `ffmpeg -version` for the binary the command runs
+pub fn version(program: &str) -> Option<String> {
+    let output = Command::new(program).arg("-version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    stdout.lines().next().map(String::from)
+}
+
+impl Report {
+    /// Report without the FFmpeg version, which takes running the binary to find out
+    pub fn new(
+        stack: Stack,
+        primed: &[Primed],
+        probes: &[Probed],
+        ffmpeg: &Command,
+        output: &str,
+        elapsed: Duration,
+        exit_code: Option<i32>,
+    ) -> Report {
+        Report {
+            created: Local::now().to_rfc3339(),
+            ffmpeg_version: None,
+            stack,
+            inputs: primed
+                .iter()
+                .zip(probes)
+                .map(|(prime, probed)| Input {
+                    path: prime.path.clone(),
+                    probe: probed.clone(),
+                })
+                .collect(),
+            tiles: primed.iter().map(Tile::from).collect(),
+            args: ffmpeg
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            output: output.into(),
+            output_size: output_size(output),
+            elapsed: elapsed.as_secs_f64(),
+            exit_code,
+        }
+    }
+
+    /// Sidecar path for an output, e.g. `review.report.json` for `review.mkv`,
+    /// then `review.report-2.json` and on when incrementing
+    fn path(output: &str, counter: u32) -> PathBuf {
+        match counter > 1 {
+            true => Path::new(output).with_extension(format!("report-{}.json", counter)),
+            false => Path::new(output).with_extension("report.json"),
+        }
+    }
+
+    /// Write the report as pretty JSON next to the output, settling an existing report
+    /// the way `collision` settled the output
+    pub fn write(&self, collision: Collision) -> io::Result<PathBuf> {
+        let create_new = |path: &Path| OpenOptions::new().write(true).create_new(true).open(path);
+
+        let (path, file) = match collision {
+            // Asking already confirmed replacing the output, its report goes with it
+            Collision::Overwrite | Collision::Ask => {
+                let path = Report::path(&self.output, 1);
+                let file = File::create(&path)?;
+                (path, file)
+            }
+            Collision::Fail => {
+                let path = Report::path(&self.output, 1);
+                let file = create_new(&path)?;
+                (path, file)
+            }
+            Collision::Increment => {
+                let mut created = None;
+                for counter in 1..=MAX_COUNTER {
+                    let path = Report::path(&self.output, counter);
+                    match create_new(&path) {
+                        Ok(file) => {
+                            created = Some((path, file));
+                            break;
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+                created.ok_or(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "every report name is taken",
+                ))?
+            }
+        };
+
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::subtitling::SubtitleSource;
+    use super::super::testing::TempDir;
+    use super::*;
+
+    #[test]
+    fn it_reports() {
+        let primed = vec![Primed {
+            path: String::from("videos/a.mov"),
+            start: Some(5),
+            end: Some(15),
+            speed: Some(2.0),
+            visualization: Some(Visualization::Spectrum),
+            subtitles: Some(Subtitles {
+                source: SubtitleSource::Embedded { index: 1 },
+                style: Default::default(),
+            }),
+            ..Default::default()
+        }];
+        let probes = vec![Probed::default()];
+
+        let mut ffmpeg = Command::new("ffmpeg");
+        ffmpeg.args(["-i", "videos/a.mov", "out/review.mkv"]);
+
+        let report = Report::new(
+            Stack::Horizontal,
+            &primed,
+            &probes,
+            &ffmpeg,
+            "out/review.mkv",
+            Duration::from_millis(1500),
+            Some(0),
+        );
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["stack"], "horizontal");
+        assert_eq!(json["args"][1], "videos/a.mov");
+        assert_eq!(json["tiles"][0]["speed"], 2.0);
+        assert_eq!(json["tiles"][0]["visualization"], "spectrum");
+        assert_eq!(json["tiles"][0]["subtitles"]["source"], "embedded");
+        assert_eq!(json["tiles"][0]["subtitles"]["index"], 1);
+        assert_eq!(json["elapsed"], 1.5);
+        assert!(json["output_size"].is_null());
+        assert!(json["ffmpeg_version"].is_null());
+
+        assert_eq!(
+            Report::path("out/review.mkv", 1),
+            PathBuf::from("out/review.report.json")
+        );
+    }
+
+    #[test]
+    fn it_sums_segmented_outputs() {
+        let dir = TempDir::new("reporting");
+        let files = [
+            ("review.m3u8", 10),
+            ("review_0.m3u8", 5),
+            ("review_0_000.ts", 100),
+            ("review_0_001.ts", 100),
+            ("other_0_000.ts", 1000),
+            ("review.mkv", 50),
+        ];
+        for (name, size) in files {
+            fs::write(dir.join(name), vec![0; size]).unwrap();
+        }
+
+        let size = |name: &str| output_size(&dir.join(name).to_string_lossy());
+        assert_eq!(size("review.m3u8"), Some(215));
+        assert_eq!(size("review.mkv"), Some(50));
+        assert_eq!(size("missing.mpd"), None);
+    }
+
+    #[test]
+    fn it_settles_report_collisions() {
+        let dir = TempDir::new("reporting");
+
+        let output = dir.join("review.mkv").to_string_lossy().into_owned();
+        let report = Report::new(
+            Stack::Vertical,
+            &[],
+            &[],
+            &Command::new("ffmpeg"),
+            &output,
+            Duration::ZERO,
+            Some(0),
+        );
+
+        let first = report.write(Collision::Fail).unwrap();
+        assert_eq!(first, dir.join("review.report.json"));
+        assert!(report.write(Collision::Fail).is_err());
+        assert_eq!(
+            report.write(Collision::Increment).unwrap(),
+            dir.join("review.report-2.json")
+        );
+        assert_eq!(report.write(Collision::Overwrite).unwrap(), first);
+    }
+}
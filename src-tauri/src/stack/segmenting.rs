@@ -0,0 +1,265 @@
+use super::filtering::{Chain, Filter, Pad};
+
+use core::fmt;
+use serde::Deserialize;
+use std::path::Path;
+
+fn default_segment_duration() -> f64 {
+    4.0
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Package {
+    Hls,
+    Dash,
+}
+
+impl Package {
+    /// Extension of the playlist or manifest
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Package::Hls => "m3u8",
+            Package::Dash => "mpd",
+        }
+    }
+}
+
+impl fmt::Display for Package {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Package::Hls => write!(f, "HLS"),
+            Package::Dash => write!(f, "DASH"),
+        }
+    }
+}
+
+/// Additional encode of the stack at a lower size
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Rendition {
+    pub height: u16,
+    // Kilobits per second, left to the encoder when not set
+    #[serde(default)]
+    pub bitrate: Option<u32>,
+}
+
+/// Segmented output received from the GUI.
+/// Without renditions the stack is packaged once at its own size.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Segmenting {
+    pub package: Package,
+    #[serde(default = "default_segment_duration")]
+    pub segment_duration: f64,
+    #[serde(default)]
+    pub renditions: Vec<Rendition>,
+}
+
+impl Segmenting {
+    /// Pads every video rendition is encoded from
+    pub fn pads(&self, input: &Pad) -> Vec<Pad> {
+        match self.renditions.len() {
+            0 => vec![input.clone()],
+            n => (0..n).map(|idx| Pad::named(&format!("r{}", idx))).collect(),
+        }
+    }
+
+    /// Chains scaling the stacked video at `input` into the renditions
+    pub fn chains(&self, input: Pad) -> Vec<Chain> {
+        let pads = self.pads(&input);
+        let scale = |height: u16| Filter::new("scale").option("w", -2).option("h", height);
+
+        match self.renditions.as_slice() {
+            [] => Vec::new(),
+            [rendition] => vec![Chain::new()
+                .inputs(vec![input])
+                .filter(scale(rendition.height))
+                .output(pads[0].clone())],
+            renditions => {
+                let splits: Vec<Pad> = (0..renditions.len())
+                    .map(|idx| Pad::named(&format!("s{}", idx)))
+                    .collect();
+
+                let split = splits.iter().cloned().fold(
+                    Chain::new()
+                        .inputs(vec![input])
+                        .filter(Filter::new("split").option("outputs", renditions.len())),
+                    Chain::output,
+                );
+
+                let scales =
+                    renditions
+                        .iter()
+                        .zip(splits)
+                        .zip(pads)
+                        .map(|((rendition, split), pad)| {
+                            Chain::new()
+                                .inputs(vec![split])
+                                .filter(scale(rendition.height))
+                                .output(pad)
+                        });
+
+                [split].into_iter().chain(scales).collect()
+            }
+        }
+    }
+
+    /// Mapping, encoding and muxer arguments ending with the output path.
+    /// Segments are named after the playlist and written next to it.
+    pub fn args(&self, video: &[Pad], audio: Option<&str>, playlist: &Path) -> Vec<String> {
+        let dir = playlist.parent().unwrap_or(Path::new(""));
+        let stem = playlist
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut args = Vec::new();
+        for pad in video {
+            args.extend([String::from("-map"), pad.to_string()]);
+        }
+        if let Some(audio) = audio {
+            args.extend([String::from("-map"), audio.into()]);
+        }
+
+        args.extend(["-c:v", "libx264", "-c:a", "aac"].map(String::from));
+        for (idx, rendition) in self.renditions.iter().enumerate() {
+            if let Some(bitrate) = rendition.bitrate {
+                args.extend([format!("-b:v:{}", idx), format!("{}k", bitrate)]);
+            }
+        }
+
+        // Keyframes on every segment boundary so renditions switch cleanly
+        args.extend([
+            String::from("-force_key_frames"),
+            format!("expr:gte(t,n_forced*{})", self.segment_duration),
+        ]);
+
+        match self.package {
+            Package::Hls => {
+                // Audio is shared by every variant as a separate rendition group
+                let group = audio.map(|_| ",agroup:audio").unwrap_or_default();
+                let mut streams: Vec<String> = (0..video.len())
+                    .map(|idx| format!("v:{}{}", idx, group))
+                    .collect();
+                if audio.is_some() {
+                    streams.push(String::from("a:0,agroup:audio"));
+                }
+
+                let segments = dir.join(format!("{}_%v_%03d.ts", stem));
+                let variants = dir.join(format!("{}_%v.m3u8", stem));
+
+                args.extend([
+                    String::from("-f"),
+                    String::from("hls"),
+                    String::from("-hls_time"),
+                    self.segment_duration.to_string(),
+                    String::from("-hls_playlist_type"),
+                    String::from("vod"),
+                    String::from("-hls_segment_filename"),
+                    segments.to_string_lossy().into_owned(),
+                    String::from("-master_pl_name"),
+                    format!("{}.m3u8", stem),
+                    String::from("-var_stream_map"),
+                    streams.join(" "),
+                    variants.to_string_lossy().into_owned(),
+                ]);
+            }
+            Package::Dash => {
+                let sets = match audio {
+                    Some(_) => "id=0,streams=v id=1,streams=a",
+                    None => "id=0,streams=v",
+                };
+
+                args.extend([
+                    String::from("-f"),
+                    String::from("dash"),
+                    String::from("-seg_duration"),
+                    self.segment_duration.to_string(),
+                    String::from("-use_template"),
+                    String::from("1"),
+                    String::from("-use_timeline"),
+                    String::from("1"),
+                    String::from("-init_seg_name"),
+                    format!("{}_init_$RepresentationID$.m4s", stem),
+                    String::from("-media_seg_name"),
+                    format!("{}_$RepresentationID$_$Number%05d$.m4s", stem),
+                    String::from("-adaptation_sets"),
+                    String::from(sets),
+                    playlist.to_string_lossy().into_owned(),
+                ]);
+            }
+        }
+
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segmenting(package: Package) -> Segmenting {
+        Segmenting {
+            package,
+            segment_duration: 4.0,
+            renditions: vec![
+                Rendition {
+                    height: 720,
+                    bitrate: Some(3000),
+                },
+                Rendition {
+                    height: 360,
+                    bitrate: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn it_splits_renditions() {
+        let chains = segmenting(Package::Hls).chains(Pad::named("v"));
+        let result: Vec<String> = chains.iter().map(Chain::to_string).collect();
+
+        assert_eq!(
+            result,
+            [
+                "[v]split=outputs=2[s0][s1]",
+                "[s0]scale=w=-2:h=720[r0]",
+                "[s1]scale=w=-2:h=360[r1]"
+            ]
+        );
+
+        let single = Segmenting {
+            renditions: Vec::new(),
+            ..segmenting(Package::Hls)
+        };
+        assert!(single.chains(Pad::named("v")).is_empty());
+        assert_eq!(single.pads(&Pad::named("v")), [Pad::named("v")]);
+    }
+
+    #[test]
+    fn it_packages_hls() {
+        let hls = segmenting(Package::Hls);
+        let pads = hls.pads(&Pad::named("v"));
+        let result = hls.args(&pads, Some("0:a:0"), Path::new("out/review.m3u8"));
+
+        assert_eq!(
+            result.join(" "),
+            "-map [r0] -map [r1] -map 0:a:0 -c:v libx264 -c:a aac -b:v:0 3000k \
+             -force_key_frames expr:gte(t,n_forced*4) -f hls -hls_time 4 \
+             -hls_playlist_type vod -hls_segment_filename out/review_%v_%03d.ts \
+             -master_pl_name review.m3u8 \
+             -var_stream_map v:0,agroup:audio v:1,agroup:audio a:0,agroup:audio \
+             out/review_%v.m3u8"
+        );
+    }
+
+    #[test]
+    fn it_packages_dash() {
+        let dash = segmenting(Package::Dash);
+        let result = dash.args(&[Pad::named("r0")], None, Path::new("out/review.mpd"));
+
+        assert!(result
+            .join(" ")
+            .ends_with("-adaptation_sets id=0,streams=v out/review.mpd"));
+    }
+}
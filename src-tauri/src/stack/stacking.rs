@@ -1,13 +1,26 @@
-use super::priming::{Duration, Primed};
-use super::{Execution, Handle};
+use super::animating::Animation;
+use super::correcting::Preset;
+use super::exporting;
+use super::filtering::{Chain, Filter, Graph, Pad};
+use super::naming::{Collision, Destination, Fields, NamingError};
+use super::priming::{Duration, PlaybackMode, Primed, LOOP_FRAMES};
+use super::probing::{Probed, ProbedDimensions};
+use super::reporting::{self, Report};
+use super::segmenting::Segmenting;
+use super::streaming::Target;
+use super::subtitling::Subtitles;
+use super::tagging::Metadata;
+use super::watermarking::Watermark;
+use super::{tail, ExecuteError, Execution, Handle};
 
+use chrono::Local;
 use core::fmt;
-use rand::distributions::Alphanumeric;
-use rand::{thread_rng, Rng};
-use std::ffi::OsStr;
-use std::fmt::Write;
-use std::path::PathBuf;
-use std::process::Command;
+use log::error;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
 
 pub trait StackIdentity {
     fn identify(&self) -> Stack;
@@ -27,13 +40,47 @@ impl StackIdentity for Vec<Primed> {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Stack {
     X,
     Horizontal,
     Vertical,
 }
 
+impl Stack {
+    /// Tiles must line up as FFmpeg doesn't scale them to fit the stack
+    pub fn check(&self, probes: &[Probed]) -> Result<(), String> {
+        match self {
+            Stack::Vertical if !probes.is_same_width() => Err(String::from(
+                "Unable to process vertical stack with mismatched width",
+            )),
+            Stack::Horizontal if !probes.is_same_height() => Err(String::from(
+                "Unable to process horizontal stack with mismatched height",
+            )),
+            Stack::X if !probes.is_same_dimensions() => Err(String::from(
+                "Unable to process X stack with mismatched dimensions",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stack::X => write!(f, "xstack"),
+            Stack::Horizontal => write!(f, "hstack"),
+            Stack::Vertical => write!(f, "vstack"),
+        }
+    }
+}
+
+/// Number of columns used for a near-square mosaic of `n` tiles
+pub fn columns(n: usize) -> usize {
+    ((n as f64).sqrt().floor() as usize).max(1)
+}
+
 struct Xstack {
     n: usize,
 }
@@ -43,13 +90,10 @@ impl Xstack {
         Xstack { n }
     }
 
-    fn compose(&self) -> String {
-        format!(
-            "{}xstack=inputs={}:layout='{}'[v]",
-            self.gen_labels(),
-            self.n,
-            self.gen_layout()
-        )
+    fn compose(&self) -> Filter {
+        Filter::new("xstack")
+            .option("inputs", self.n)
+            .option("layout", self.gen_layout())
     }
 
     fn gen_offset(char: String, offset: usize) -> String {
@@ -63,15 +107,8 @@ impl Xstack {
             .join("+")
     }
 
-    fn gen_labels(&self) -> String {
-        (0..self.n).fold(String::new(), |mut output, idx| {
-            let _ = write!(output, "[{idx}:v]");
-            output
-        })
-    }
-
     fn gen_layout(&self) -> String {
-        let cols = (self.n as f64).sqrt().floor() as usize;
+        let cols = columns(self.n);
 
         (0..self.n)
             .map(|idx| {
@@ -87,24 +124,233 @@ impl Xstack {
     }
 }
 
+/// Tempo factors accepted by a single atempo filter
+const ATEMPO_RANGE: (f64, f64) = (0.5, 2.0);
+
+/// Split a speed factor into atempo stages that each stay within range
+fn atempo_chain(speed: f64) -> Vec<f64> {
+    let (min, max) = ATEMPO_RANGE;
+    let mut stages = Vec::new();
+    let mut remaining = speed;
+
+    while remaining > max {
+        stages.push(max);
+        remaining /= max;
+    }
+
+    while remaining < min {
+        stages.push(min);
+        remaining /= min;
+    }
+
+    stages.push(remaining);
+    stages
+}
+
+// Samples the audio loop filter may buffer
+const LOOP_SAMPLES: i32 = i32::MAX;
+
+/// Stream of a tile being filtered
+#[derive(Debug, Clone, Copy)]
+enum Media {
+    Video,
+    Audio,
+}
+
+impl Media {
+    fn stream(&self, idx: usize) -> Pad {
+        match self {
+            Media::Video => Pad::stream(idx, "v"),
+            Media::Audio => Pad::stream(idx, "a:0"),
+        }
+    }
+
+    fn prefix(&self) -> &str {
+        match self {
+            Media::Video => "",
+            Media::Audio => "a",
+        }
+    }
+
+    // Audio counterparts share the video filter name with an `a` prefix
+    fn filter(&self, name: &str) -> Filter {
+        Filter::new(&format!("{}{}", self.prefix(), name))
+    }
+
+    fn concat(&self) -> Filter {
+        let (video, audio) = match self {
+            Media::Video => (1, 0),
+            Media::Audio => (0, 1),
+        };
+
+        Filter::new("concat")
+            .option("n", 2)
+            .option("v", video)
+            .option("a", audio)
+    }
+
+    fn loop_size(&self) -> i32 {
+        match self {
+            Media::Video => LOOP_FRAMES,
+            Media::Audio => LOOP_SAMPLES,
+        }
+    }
+}
+
 /// Wrapper for FFmpeg stack execution
 pub struct Stacker {
     stack: Stack,
     primed: Vec<Primed>,
+    probes: Vec<Probed>,
     ffmpeg: Command,
     output: String,
+    collision: Collision,
+    animation: Option<Animation>,
+    segmenting: Option<Segmenting>,
+    streaming: Option<Target>,
+    subtitles: Option<Subtitles>,
+    watermark: Option<Watermark>,
+    metadata: Metadata,
+    // Write progress to stdout for `run` to read
+    progress: bool,
+}
+
+/// Lines of FFmpeg's log kept to explain a failed run
+const LOG_TAIL: usize = 20;
+
+/// Seconds of output written so far, from a line of `-progress` output
+fn parse_progress(line: &str) -> Option<f64> {
+    let micros: u64 = line.strip_prefix("out_time_us=")?.trim().parse().ok()?;
+    Some(micros as f64 / 1_000_000.0)
 }
 
 impl Stacker {
-    pub fn new(primed: Vec<Primed>, output: &str) -> Stacker {
-        Stacker {
-            stack: primed.identify(),
+    pub fn new(
+        mut tiles: Vec<(Primed, Probed)>,
+        destination: &Destination,
+    ) -> Result<Stacker, NamingError> {
+        let stack = tiles
+            .iter()
+            .map(|(primed, _)| primed.clone())
+            .collect::<Vec<_>>()
+            .identify();
+
+        match stack {
+            Stack::Horizontal => tiles.sort_by_key(|(f, _)| f.x),
+            Stack::Vertical => tiles.sort_by_key(|(f, _)| f.y),
+            Stack::X => tiles.sort_by_key(|(f, _)| (f.y, f.x)), // Row Major Order Mosaic
+        }
+
+        let (primed, probes): (Vec<_>, Vec<_>) = tiles.into_iter().unzip();
+        let output = Stacker::create_output_path(&stack, &primed, destination)?;
+
+        Ok(Stacker {
+            stack,
             primed,
+            probes,
             ffmpeg: Command::new("ffmpeg"),
-            output: Stacker::create_output_path(output),
+            output,
+            collision: destination.collision,
+            animation: destination.animation,
+            segmenting: destination.segmenting.clone(),
+            streaming: destination.streaming.clone(),
+            subtitles: destination.subtitles.clone(),
+            watermark: destination.watermark.clone(),
+            metadata: destination.metadata.clone(),
+            progress: false,
+        })
+    }
+
+    /// Execute while reporting the fraction of the output written so far. Measured against
+    /// `duration`, so tiles changing speed or playback count as the output sees them.
+    pub fn run(&mut self, mut progress: impl FnMut(f64)) -> Result<ExitStatus, ExecuteError> {
+        let duration = self.duration();
+        self.progress = true;
+
+        let spawned = self
+            .assemble()
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = spawned.map_err(|e| {
+            error!("Spawning command failed: {e}");
+            ExecuteError::Spawn
+        })?;
+
+        let log = child.stderr.take().map(|stderr| tail(stderr, LOG_TAIL));
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if let (Some(written), true) = (parse_progress(&line), duration > 0.0) {
+                    progress((written / duration).min(1.0));
+                }
+            }
+        }
+
+        let status = child.wait().map_err(|e| {
+            error!("Failed to wait on child: {e}");
+            ExecuteError::OutputWait
+        })?;
+        let log = log.and_then(|log| log.join().ok()).unwrap_or_default();
+
+        match status.success() {
+            true => Ok(status),
+            false => {
+                error!("Command failed with {}: {}", status, log.join("\n"));
+                Err(ExecuteError::Execution)
+            }
         }
     }
 
+    /// Length of the stacked output, the longest tile once trimmed and sped up
+    pub fn duration(&self) -> f64 {
+        self.primed
+            .iter()
+            .zip(&self.probes)
+            .map(|(primed, probed)| primed.output_duration(probed.duration))
+            .fold(0.0, f64::max)
+    }
+
+    /// Width and height of the stacked frame
+    pub fn dimensions(&self) -> (u32, u32) {
+        let widths = self.probes.iter().map(|probed| probed.width as u32);
+        let heights = self.probes.iter().map(|probed| probed.height as u32);
+
+        match self.stack {
+            Stack::Horizontal => (widths.sum(), heights.max().unwrap_or_default()),
+            Stack::Vertical => (widths.max().unwrap_or_default(), heights.sum()),
+            Stack::X => {
+                // Tiles share dimensions, so the mosaic is a grid of the first one
+                let cols = columns(self.probes.len());
+                let rows = self.probes.len().div_ceil(cols);
+                let (width, height) = self
+                    .probes
+                    .first()
+                    .map(|probed| (probed.width as u32, probed.height as u32))
+                    .unwrap_or_default();
+
+                (width * cols as u32, height * rows as u32)
+            }
+        }
+    }
+
+    /// Rough size of an animated output, `None` for video
+    pub fn estimated_size(&self) -> Option<u64> {
+        let (width, height) = self.dimensions();
+
+        self.animation
+            .map(|animation| animation.estimate_size(width, height, self.duration()))
+    }
+
+    // Same pick FFmpeg makes without explicit mappings: most channels, first in grid order on ties
+    fn audio_source(&self) -> Option<usize> {
+        self.probes
+            .iter()
+            .enumerate()
+            .filter(|(_, probed)| probed.audio)
+            .max_by_key(|(idx, probed)| (probed.channels, Reverse(*idx)))
+            .map(|(idx, _)| idx)
+    }
+
     fn arg_trimmings(&mut self) -> &mut Command {
         for prime in self.primed.iter() {
             // Range sliders enforces that start and end always exist together
@@ -112,32 +358,388 @@ impl Stacker {
                 self.ffmpeg.args(["-ss", &start.as_ts()]);
                 self.ffmpeg.args(["-to", &end.as_ts()]);
             }
+            // Streams are read at their native rate so the endpoint plays in realtime
+            if self.streaming.is_some() {
+                self.ffmpeg.arg("-re");
+            }
             self.ffmpeg.args(["-i", &prime.path]);
         }
 
+        // The watermark comes after every tile so tile indices stay put
+        if let Some(watermark) = &self.watermark {
+            self.ffmpeg.args(["-i", &watermark.path]);
+        }
+
         &mut self.ffmpeg
     }
 
-    fn rand_fname() -> String {
-        // https://rust-lang-nursery.github.io/rust-cookbook/algorithms/randomness.html
-        let rand_str: String = thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(30)
-            .map(char::from)
-            .collect();
+    fn create_output_path(
+        stack: &Stack,
+        primed: &[Primed],
+        destination: &Destination,
+    ) -> Result<String, NamingError> {
+        if let Some(target) = &destination.streaming {
+            return Ok(target.url.clone());
+        }
 
-        format!("stacked-{}", rand_str)
-    }
+        let stem = primed
+            .first()
+            .and_then(|prime| Path::new(&prime.path).file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let fields = Fields {
+            timestamp: Local::now(),
+            layout: stack.to_string(),
+            count: primed.len(),
+            stem,
+        };
+
+        let extension = match (&destination.animation, &destination.segmenting) {
+            (Some(animation), _) => animation.format.extension(),
+            (None, Some(segmenting)) => segmenting.package.extension(),
+            (None, None) => "mkv",
+        };
 
-    fn create_output_path(output: &str) -> String {
-        let mut path = PathBuf::from(output);
-        path.push(format!("{}.mkv", Stacker::rand_fname()));
-        path.to_string_lossy().into_owned()
+        let path = destination.resolve(&fields, extension)?;
+        Ok(path.to_string_lossy().into_owned())
     }
 
+    /// Output file, the playlist of a segmented output or the URL of a stream
     pub fn output_path(&self) -> String {
         self.output.clone()
     }
+
+    pub fn segmenting(&self) -> Option<&Segmenting> {
+        self.segmenting.as_ref()
+    }
+
+    /// Record of this render once FFmpeg has exited with `status` after `elapsed`
+    pub fn report(&self, elapsed: std::time::Duration, status: ExitStatus) -> Report {
+        let mut report = Report::new(
+            self.stack,
+            &self.primed,
+            &self.probes,
+            &self.ffmpeg,
+            &self.output,
+            elapsed,
+            status.code(),
+        );
+        report.ffmpeg_version = reporting::version(&self.ffmpeg.get_program().to_string_lossy());
+        report
+    }
+
+    /// Write the assembled command out as a shell script instead of executing it
+    pub fn write_script(&self, path: &str) -> io::Result<PathBuf> {
+        exporting::write_script(path, &self.ffmpeg)
+    }
+
+    /// Filters applied to a single tile before its playback mode, cropping ahead of
+    /// the presets so crop coordinates stay in source frame space.
+    /// Subtitles come last so they're never cropped away or turned sideways.
+    fn tile_filters(prime: &Primed) -> Vec<Filter> {
+        let mut filters = Vec::new();
+
+        if let Some(rect) = prime.crop {
+            filters.push(
+                Filter::new("crop")
+                    .option("w", rect.width)
+                    .option("h", rect.height)
+                    .option("x", rect.x)
+                    .option("y", rect.y),
+            );
+        }
+
+        filters.extend(prime.presets.iter().flat_map(Preset::filters));
+
+        if let Some(subtitles) = &prime.subtitles {
+            // Burned before the speed changes, so timing stays in source time
+            filters.extend(subtitles.filters(&prime.path, prime.start, None));
+        }
+
+        filters
+    }
+
+    fn speed_filters(media: Media, speed: Option<f64>) -> Vec<Filter> {
+        let Some(speed) = speed else {
+            return Vec::new();
+        };
+
+        match media {
+            Media::Video => vec![Filter::new("setpts").option("expr", format!("PTS/{}", speed))],
+            Media::Audio => atempo_chain(speed)
+                .into_iter()
+                .map(|tempo| Filter::new("atempo").option("tempo", tempo))
+                .collect(),
+        }
+    }
+
+    /// Chains taking one stream of a tile to `output`, with the playback mode applied
+    /// between `pre` and `post`. Empty when the stream can be used as is.
+    fn tile_chains(
+        idx: usize,
+        media: Media,
+        input: Pad,
+        playback: Option<PlaybackMode>,
+        pre: Vec<Filter>,
+        post: Vec<Filter>,
+        output: Pad,
+    ) -> Vec<Chain> {
+        let input = Chain::new().inputs(vec![input]);
+        let pad = |name: &str| Pad::named(&format!("{}{}{}", media.prefix(), name, idx));
+
+        let mut middle = Vec::new();
+        match playback {
+            Some(PlaybackMode::Boomerang) => {
+                let (forward, reverse, reversed) = (pad("f"), pad("r"), pad("b"));
+
+                let split = pre
+                    .into_iter()
+                    .fold(input, Chain::filter)
+                    .filter(media.filter("split"))
+                    .output(forward.clone())
+                    .output(reverse.clone());
+                let reverse = Chain::new()
+                    .inputs(vec![reverse])
+                    .filter(media.filter("reverse"))
+                    .output(reversed.clone());
+                let concat = post
+                    .into_iter()
+                    .fold(
+                        Chain::new()
+                            .inputs(vec![forward, reversed])
+                            .filter(media.concat()),
+                        Chain::filter,
+                    )
+                    .output(output);
+
+                return vec![split, reverse, concat];
+            }
+            Some(PlaybackMode::Reverse) => middle.push(media.filter("reverse")),
+            Some(PlaybackMode::Loop { count }) if count > 1 => middle.push(
+                media
+                    .filter("loop")
+                    .option("loop", count - 1)
+                    .option("size", media.loop_size())
+                    .option("start", 0),
+            ),
+            _ => {}
+        }
+
+        let chain = pre
+            .into_iter()
+            .chain(middle)
+            .chain(post)
+            .fold(input, Chain::filter);
+
+        if chain.is_empty() {
+            return Vec::new();
+        }
+
+        vec![chain.output(output)]
+    }
+
+    fn filtergraph(&self) -> Graph {
+        let n = self.primed.len();
+        let mut graph = Graph::new();
+        let mut pads = Vec::new();
+
+        // Tiles without filters feed the stack straight from their input
+        for (idx, (prime, probed)) in self.primed.iter().zip(&self.probes).enumerate() {
+            let input = match probed.audio_only {
+                // Audio-only inputs are drawn at the size the tile was given when probed
+                true => {
+                    let pad = Pad::named(&format!("w{}", idx));
+                    let visualization = prime.visualization.unwrap_or_default().chains(
+                        idx,
+                        Media::Audio.stream(idx),
+                        probed.width,
+                        probed.height,
+                        pad.clone(),
+                    );
+
+                    graph = visualization.into_iter().fold(graph, Graph::chain);
+                    pad
+                }
+                false => Media::Video.stream(idx),
+            };
+
+            let pad = Pad::named(&format!("t{}", idx));
+            let chains = Stacker::tile_chains(
+                idx,
+                Media::Video,
+                input.clone(),
+                prime.playback,
+                Stacker::tile_filters(prime),
+                Stacker::speed_filters(Media::Video, prime.speed),
+                pad.clone(),
+            );
+
+            if chains.is_empty() {
+                pads.push(input);
+                continue;
+            }
+
+            graph = chains.into_iter().fold(graph, Graph::chain);
+            pads.push(pad);
+        }
+
+        let chain = Chain::new().inputs(pads);
+        let chain = match self.stack {
+            Stack::Horizontal => chain.filter(Filter::new("hstack").option("inputs", n)),
+            Stack::Vertical => chain.filter(Filter::new("vstack").option("inputs", n)),
+            Stack::X => chain.filter(Xstack::new(n).compose()),
+        };
+
+        let mosaic = self.mosaic_chains(Pad::named("m"), Pad::named("v"));
+        let graph = match mosaic.is_empty() {
+            true => graph.chain(chain.output(Pad::named("v"))),
+            false => mosaic
+                .into_iter()
+                .fold(graph.chain(chain.output(Pad::named("m"))), Graph::chain),
+        };
+        let chains = match (self.animation, &self.segmenting) {
+            (Some(animation), _) => animation.chains(Pad::named("v"), Pad::named("g")),
+            (None, Some(segmenting)) => segmenting.chains(Pad::named("v")),
+            (None, None) => Vec::new(),
+        };
+        let graph = chains.into_iter().fold(graph, Graph::chain);
+
+        self.audio_chains().into_iter().fold(graph, Graph::chain)
+    }
+
+    // Tile whose clock the mosaic subtitles follow
+    fn timing_tile(&self) -> &Primed {
+        &self.primed[self.audio_source().unwrap_or_default()]
+    }
+
+    /// Mosaic subtitles can follow a trimmed or sped up tile, but not one played out of order
+    pub fn check_subtitles(&self) -> Result<(), String> {
+        let prime = self.timing_tile();
+
+        match (&self.subtitles, prime.playback) {
+            (Some(_), Some(mode)) => Err(format!(
+                "Unable to time mosaic subtitles to {}, which plays with {}. \
+                 Clear its playback mode or burn the subtitles into a tile instead.",
+                prime.path, mode
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Chains drawing over the stacked `input`, subtitles first and the watermark on top.
+    /// Empty when the mosaic is output as stacked.
+    fn mosaic_chains(&self, input: Pad, output: Pad) -> Vec<Chain> {
+        // Mosaic subtitles are timed like the tile whose audio is heard
+        let burn = self.subtitles.as_ref().map(|subtitles| {
+            let prime = self.timing_tile();
+            subtitles.filters(&prime.path, prime.start, prime.speed)
+        });
+
+        let Some(watermark) = &self.watermark else {
+            return burn
+                .map(|filters| {
+                    let chain = Chain::new().inputs(vec![input]);
+                    vec![filters
+                        .into_iter()
+                        .fold(chain, Chain::filter)
+                        .output(output)]
+                })
+                .unwrap_or_default();
+        };
+
+        let (width, _) = self.dimensions();
+        let logo = Pad::named("l");
+        let mut chains =
+            vec![watermark.chain(Pad::stream(self.primed.len(), "v"), width, logo.clone())];
+
+        let base = match burn {
+            Some(filters) => {
+                let pad = Pad::named("s");
+                let chain = Chain::new().inputs(vec![input]);
+                chains.push(
+                    filters
+                        .into_iter()
+                        .fold(chain, Chain::filter)
+                        .output(pad.clone()),
+                );
+                pad
+            }
+            None => input,
+        };
+
+        chains.push(
+            Chain::new()
+                .inputs(vec![base, logo])
+                .filter(watermark.overlay())
+                .output(output),
+        );
+        chains
+    }
+
+    // Audio only needs filtering when its tile changes speed or playback
+    fn audio_chains(&self) -> Vec<Chain> {
+        if self.animation.is_some() {
+            return Vec::new();
+        }
+
+        let Some(idx) = self.audio_source() else {
+            return Vec::new();
+        };
+        let prime = &self.primed[idx];
+
+        Stacker::tile_chains(
+            idx,
+            Media::Audio,
+            Media::Audio.stream(idx),
+            prime.playback,
+            Vec::new(),
+            Stacker::speed_filters(Media::Audio, prime.speed),
+            Pad::named("a"),
+        )
+    }
+
+    /// Stream mappings and muxer options, ending with the output
+    fn arg_outputs(&mut self) -> &mut Command {
+        if let Some(animation) = self.animation {
+            return self
+                .ffmpeg
+                .args(["-map", "[g]"])
+                .args(animation.args())
+                .arg(&self.output);
+        }
+
+        let audio = self.audio_map();
+        let tags = self.metadata.args(&self.primed, audio.is_some());
+
+        // Tags are output options, so they only need to come before the segmented output path
+        if let Some(segmenting) = &self.segmenting {
+            let pads = segmenting.pads(&Pad::named("v"));
+            let args = segmenting.args(&pads, audio.as_deref(), Path::new(&self.output));
+            return self.ffmpeg.args(tags).args(args);
+        }
+
+        self.ffmpeg.args(["-map", "[v]"]);
+        if let Some(audio) = &audio {
+            self.ffmpeg.args(["-map", audio]);
+        }
+        self.ffmpeg.args(tags);
+
+        match &self.streaming {
+            Some(target) => self.ffmpeg.args(target.args()),
+            None => self.ffmpeg.arg(&self.output),
+        }
+    }
+
+    // Filtered audio comes from the graph, otherwise straight from its input
+    fn audio_map(&self) -> Option<String> {
+        let idx = self.audio_source()?;
+
+        match self.audio_chains().is_empty() {
+            true => Some(format!("{}:a:0", idx)),
+            false => Some(String::from("[a]")),
+        }
+    }
 }
 
 impl Execution for Stacker {
@@ -145,44 +747,39 @@ impl Execution for Stacker {
     const HANDLE: Handle = Handle::Err;
 
     fn assemble(&mut self) -> &mut Command {
-        let n = self.primed.len();
+        let graph = self.filtergraph();
 
-        match self.stack {
-            Stack::Horizontal => {
-                self.primed.sort_by_key(|f| f.x);
-                self.arg_trimmings()
-                    .arg("-filter_complex")
-                    .arg(format!("hstack=inputs={}", n));
-            }
-            Stack::Vertical => {
-                self.primed.sort_by_key(|f| f.y);
-                self.arg_trimmings()
-                    .arg("-filter_complex")
-                    .arg(format!("vstack=inputs={}", n));
-            }
-            Stack::X => {
-                self.primed.sort_by_key(|f| (f.y, f.x));
-                self.arg_trimmings()
-                    .arg("-filter_complex")
-                    .arg(Xstack::new(n).compose())
-                    .args(["-map", "[v]"]);
-            } // Row Major Order Mosaic
+        // Start from a fresh command so assembling twice is harmless
+        self.ffmpeg = Command::new("ffmpeg");
+        self.ffmpeg.arg(self.collision.flag());
+        if self.progress {
+            self.ffmpeg.args(["-progress", "pipe:1", "-nostats"]);
         }
 
-        self.ffmpeg.arg(self.output_path())
+        self.arg_trimmings()
+            .arg("-filter_complex")
+            .arg(graph.to_string());
+
+        self.arg_outputs()
     }
 }
 
 impl fmt::Display for Stacker {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let args: Vec<&OsStr> = self.ffmpeg.get_args().collect();
-        write!(f, "Args: {:#?}\nIdentity: {:#?}", args, self.stack)
+        write!(f, "{}", exporting::render(&self.ffmpeg))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::super::animating::{AnimatedFormat, Dither};
+    use super::super::priming::Rect;
+    use super::super::segmenting::{Package, Rendition};
+    use super::super::subtitling::{SubtitleSource, SubtitleStyle};
+    use super::super::visualizing::Visualization;
+    use super::super::watermarking::Placement;
     use super::*;
+    use std::ffi::OsStr;
 
     // | 1.mov |
     // | 2.mov |
@@ -254,24 +851,299 @@ mod tests {
         ]
     }
 
+    fn tiles(primed: Vec<Primed>) -> Vec<(Primed, Probed)> {
+        primed
+            .into_iter()
+            .map(|prime| (prime, Probed::default()))
+            .collect()
+    }
+
     #[test]
     fn it_generates_layout() {
         let result = Xstack::new(9).gen_layout();
         let expected = "0_0|w0_0|w0+w1_0|0_h0|w0_h0|w0+w1_h0|0_h0+h1|w0_h0+h1|w0+w1_h0+h1";
+        assert_eq!(result, expected);
+
+        // Layouts of existing stacks stay put, 3 tiles in one column and 6 tiles 2 wide
+        assert_eq!(Xstack::new(3).gen_layout(), "0_0|0_h0|0_h0+h1");
+        assert_eq!(
+            Xstack::new(6).gen_layout(),
+            "0_0|w0_0|0_h0|w0_h0|0_h0+h1|w0_h0+h1"
+        );
+    }
+
+    #[test]
+    fn it_hstack_filtergraph() {
+        let stacker = Stacker::new(tiles(hstack()), &Destination::default()).unwrap();
+        let result = stacker.filtergraph().to_string();
+        let expected = "[0:v][1:v]hstack=inputs=2[v]";
         assert_eq!(result, expected)
     }
 
     #[test]
-    fn it_generates_labels() {
-        let result = Xstack::new(4).gen_labels();
-        let expected = "[0:v][1:v][2:v][3:v]";
+    fn it_crops_tiles() {
+        let mut primed = hstack();
+        primed[1].crop = Some(Rect {
+            x: 0,
+            y: 40,
+            width: 640,
+            height: 360,
+        });
+
+        let stacker = Stacker::new(tiles(primed), &Destination::default()).unwrap();
+        let result = stacker.filtergraph().to_string();
+        let expected = "[1:v]crop=w=640:h=360:x=0:y=40[t1];[0:v][t1]hstack=inputs=2[v]";
         assert_eq!(result, expected)
     }
 
+    #[test]
+    fn it_applies_presets() {
+        let mut tiles = tiles(hstack());
+        tiles[0].0.presets = vec![Preset::Vflip, Preset::Grayscale];
+        tiles[0].0.playback = Some(PlaybackMode::Reverse);
+
+        let stacker = Stacker::new(tiles, &Destination::default()).unwrap();
+        let result = stacker.filtergraph().to_string();
+        let expected = "[0:v]vflip,hue=s=0,reverse[t0];[t0][1:v]hstack=inputs=2[v]";
+        assert_eq!(result, expected)
+    }
+
+    #[test]
+    fn it_burns_subtitles() {
+        let mut tiles = tiles(hstack());
+        tiles[1].0.start = Some(5);
+        tiles[1].0.end = Some(15);
+        tiles[1].0.subtitles = Some(Subtitles {
+            source: SubtitleSource::Embedded { index: 0 },
+            style: SubtitleStyle::default(),
+        });
+        let destination = Destination {
+            subtitles: Some(Subtitles {
+                source: SubtitleSource::File {
+                    path: String::from("notes.srt"),
+                },
+                style: SubtitleStyle::default(),
+            }),
+            ..Default::default()
+        };
+
+        let stacker = Stacker::new(tiles.clone(), &destination).unwrap();
+        let result = stacker.filtergraph().to_string();
+        let expected = "[1:v]setpts=expr=PTS+5/TB,subtitles=filename=2.mov:si=0,\
+                        setpts=expr=PTS-STARTPTS[t1];[0:v][t1]hstack=inputs=2[m];\
+                        [m]subtitles=filename=notes.srt[v]";
+        assert_eq!(result, expected);
+        assert!(stacker.check_subtitles().is_ok());
+
+        // Timed like the first tile, sped up and then reversed
+        tiles[0].0.speed = Some(2.0);
+        let stacker = Stacker::new(tiles.clone(), &destination).unwrap();
+        assert!(stacker.filtergraph().to_string().ends_with(
+            "[m]setpts=expr=PTS*2,subtitles=filename=notes.srt,setpts=expr=(PTS-STARTPTS)/2[v]"
+        ));
+
+        tiles[0].0.playback = Some(PlaybackMode::Reverse);
+        let stacker = Stacker::new(tiles, &destination).unwrap();
+        assert!(stacker.check_subtitles().is_err());
+    }
+
+    #[test]
+    fn it_watermarks() {
+        let mut tiles = tiles(hstack());
+        for (_, probed) in tiles.iter_mut() {
+            *probed = Probed {
+                width: 960,
+                height: 540,
+                ..Default::default()
+            };
+        }
+        let destination = Destination {
+            watermark: Some(Watermark {
+                path: String::from("logo.png"),
+                placement: Placement::BottomLeft,
+                scale: Some(0.25),
+                opacity: 1.0,
+                margin: 24,
+            }),
+            ..Default::default()
+        };
+
+        let mut stacker = Stacker::new(tiles, &destination).unwrap();
+        let result = stacker.filtergraph().to_string();
+        let expected =
+            "[0:v][1:v]hstack=inputs=2[m];[2:v]format=pix_fmts=rgba,scale=w=480:h=-1[l];\
+                        [m][l]overlay=x=24:y=H-h-24[v]";
+        assert_eq!(result, expected);
+
+        stacker.assemble();
+        assert!(stacker.to_string().contains("-i 2.mov -i logo.png"));
+    }
+
+    #[test]
+    fn it_changes_speed() {
+        let mut tiles = tiles(hstack());
+        tiles[0].0.speed = Some(4.0);
+        tiles[0].1.audio = true;
+        tiles[0].1.duration = 60.0;
+        tiles[1].1.duration = 20.0;
+
+        let mut stacker = Stacker::new(tiles, &Destination::default()).unwrap();
+        let result = stacker.filtergraph().to_string();
+        let expected = "[0:v]setpts=expr=PTS/4[t0];[t0][1:v]hstack=inputs=2[v];\
+                        [0:a:0]atempo=tempo=2,atempo=tempo=2[a]";
+        assert_eq!(result, expected);
+        assert_eq!(stacker.duration(), 20.0);
+
+        stacker.assemble();
+        assert!(stacker.to_string().contains("-map '[v]' -map '[a]'"));
+        assert_eq!(atempo_chain(0.2), [0.5, 0.5, 0.8]);
+    }
+
+    #[test]
+    fn it_parses_progress() {
+        assert_eq!(parse_progress("out_time_us=2500000"), Some(2.5));
+        assert_eq!(parse_progress("out_time_us=N/A"), None);
+        assert_eq!(parse_progress("frame=42"), None);
+    }
+
+    #[test]
+    fn it_picks_audio_like_ffmpeg() {
+        let mut tiles = tiles(xstack());
+        for (idx, (_, probed)) in tiles.iter_mut().enumerate() {
+            probed.audio = idx > 0;
+            probed.channels = [6, 2, 6, 6][idx];
+        }
+
+        // 3.mov comes second in row major order and wins the tie with 4.mov
+        let mut stacker = Stacker::new(tiles, &Destination::default()).unwrap();
+        assert_eq!(stacker.audio_source(), Some(1));
+
+        stacker.assemble();
+        assert!(stacker.to_string().contains("-map '[v]' -map 1:a:0"));
+    }
+
+    #[test]
+    fn it_applies_playback() {
+        let mut tiles = tiles(hstack());
+        tiles[0].0.playback = Some(PlaybackMode::Boomerang);
+        tiles[0].0.speed = Some(2.0);
+        tiles[0].1.audio = true;
+        tiles[1].0.playback = Some(PlaybackMode::Loop { count: 3 });
+
+        let stacker = Stacker::new(tiles, &Destination::default()).unwrap();
+        let result = stacker.filtergraph().to_string();
+        let expected = "[0:v]split[f0][r0];[r0]reverse[b0];\
+                        [f0][b0]concat=n=2:v=1:a=0,setpts=expr=PTS/2[t0];\
+                        [1:v]loop=loop=2:size=32767:start=0[t1];\
+                        [t0][t1]hstack=inputs=2[v];\
+                        [0:a:0]asplit[af0][ar0];[ar0]areverse[ab0];\
+                        [af0][ab0]concat=n=2:v=0:a=1,atempo=tempo=2[a]";
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn it_animates() {
+        let destination = Destination {
+            path: String::from("{stem}"),
+            animation: Some(Animation {
+                format: AnimatedFormat::Webp,
+                fps: 12,
+                width: None,
+                dither: Dither::default(),
+                plays: 0,
+            }),
+            ..Default::default()
+        };
+        let mut tiles = tiles(xstack());
+        for (_, probed) in tiles.iter_mut() {
+            (probed.width, probed.height, probed.duration) = (640, 360, 20.0);
+            probed.audio = true;
+        }
+
+        let mut stacker = Stacker::new(tiles, &destination).unwrap();
+        assert_eq!(stacker.dimensions(), (1280, 720));
+        assert_eq!(stacker.estimated_size(), Some(8847360));
+
+        stacker.assemble();
+        assert!(stacker
+            .to_string()
+            .ends_with("[v];[v]fps=fps=12[g]' -map '[g]' -an -c:v libwebp -f webp -loop 0 1.webp"));
+    }
+
+    #[test]
+    fn it_segments() {
+        let destination = Destination {
+            path: String::from("portal/{stem}"),
+            segmenting: Some(Segmenting {
+                package: Package::Hls,
+                segment_duration: 6.0,
+                renditions: vec![Rendition {
+                    height: 480,
+                    bitrate: None,
+                }],
+            }),
+            metadata: Metadata {
+                title: Some(String::from("Review")),
+                sources: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut stacker = Stacker::new(tiles(hstack()), &destination).unwrap();
+        assert_eq!(stacker.output_path(), "portal/1.m3u8");
+
+        stacker.assemble();
+        let command = stacker.to_string();
+        assert!(command.contains(
+            "hstack=inputs=2[v];[v]scale=w=-2:h=480[r0]' -metadata title=Review -map '[r0]'"
+        ));
+        assert!(command.ends_with("-master_pl_name 1.m3u8 -var_stream_map v:0 portal/1_%v.m3u8"));
+    }
+
+    #[test]
+    fn it_streams() {
+        let destination = Destination {
+            path: String::from("ignored"),
+            streaming: Some(Target::try_from(String::from("udp://127.0.0.1:1234")).unwrap()),
+            ..Default::default()
+        };
+
+        let mut stacker = Stacker::new(tiles(vstack()), &destination).unwrap();
+        assert_eq!(stacker.output_path(), "udp://127.0.0.1:1234");
+
+        stacker.assemble();
+        let command = stacker.to_string();
+        assert!(command.starts_with("ffmpeg -n -re -i 1.mov -re -i 2.mov"));
+        assert!(command.ends_with("-f mpegts udp://127.0.0.1:1234"));
+    }
+
+    #[test]
+    fn it_visualizes_audio() {
+        let mut tiles = tiles(hstack());
+        tiles[1].0.visualization = Some(Visualization::Spectrum);
+        tiles[1].1 = Probed {
+            width: 640,
+            height: 360,
+            audio: true,
+            audio_only: true,
+            ..Default::default()
+        };
+
+        let mut stacker = Stacker::new(tiles, &Destination::default()).unwrap();
+        let result = stacker.filtergraph().to_string();
+        let expected = "[1:a:0]showspectrum=s=640x360:slide=scroll:fps=30,\
+                        format=pix_fmts=yuv420p[w1];[0:v][w1]hstack=inputs=2[v]";
+        assert_eq!(result, expected);
+
+        stacker.assemble();
+        assert!(stacker.to_string().contains("-map '[v]' -map 1:a:0"));
+    }
+
     #[test]
     fn it_xstack_composes() {
-        let result = Xstack::new(4).compose();
-        let expected = "[0:v][1:v][2:v][3:v]xstack=inputs=4:layout='0_0|w0_0|0_h0|w0_h0'[v]";
+        let result = Xstack::new(4).compose().to_string();
+        let expected = "xstack=inputs=4:layout=0_0|w0_0|0_h0|w0_h0";
         assert_eq!(result, expected)
     }
 
@@ -284,20 +1156,17 @@ mod tests {
 
     #[test]
     fn it_assembles() {
-        let mut stacker = Stacker::new(xstack(), "videos");
-        let mut result: Vec<&OsStr> = stacker.assemble().get_args().collect();
-
-        // Last item (output) is created with rand suffix
-        let last = result.pop();
-        assert!(last
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .starts_with("videos/stacked-"));
+        let destination = Destination {
+            path: String::from("videos/{stem}-{layout}-{count}"),
+            ..Default::default()
+        };
+        let mut stacker = Stacker::new(tiles(xstack()), &destination).unwrap();
+        let result: Vec<&OsStr> = stacker.assemble().get_args().collect();
 
         assert_eq!(
             result,
             [
+                "-n",
                 "-ss",
                 "00:00:10",
                 "-to",
@@ -311,10 +1180,20 @@ mod tests {
                 "-i",
                 "4.mov",
                 "-filter_complex",
-                "[0:v][1:v][2:v][3:v]xstack=inputs=4:layout=\'0_0|w0_0|0_h0|w0_h0\'[v]",
+                "[0:v][1:v][2:v][3:v]xstack=inputs=4:layout=0_0|w0_0|0_h0|w0_h0[v]",
                 "-map",
-                "[v]"
+                "[v]",
+                "-metadata",
+                "stacked_sources=1.mov (0,0) 00:00:10-00:00:30; 3.mov (1,0); 2.mov (0,1); 4.mov (1,1)",
+                "videos/1-xstack-4.mkv"
             ]
         );
+
+        // Assembling again must not duplicate arguments
+        let command = stacker.to_string();
+        stacker.assemble();
+        assert_eq!(stacker.to_string(), command);
+        assert!(command.starts_with("ffmpeg -n -ss 00:00:10 -to 00:00:30 -i 1.mov"));
+        assert!(command.ends_with("4.mov (1,1)' videos/1-xstack-4.mkv"));
     }
 }
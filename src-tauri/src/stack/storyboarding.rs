@@ -0,0 +1,345 @@
+use super::caching::FileKey;
+use super::detecting::SCENE_THRESHOLD;
+use super::filtering::{Chain, Filter};
+use super::naming::{Collision, Destination, Fields};
+use super::stacking::columns;
+use super::thumbnailing::ImageFormat;
+use super::{Execution, Handle};
+
+use chrono::Local;
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Gap between frames and around the sheet, in pixels
+const PADDING: u32 = 4;
+
+fn default_threshold() -> f64 {
+    SCENE_THRESHOLD
+}
+
+fn default_count() -> usize {
+    12
+}
+
+fn default_width() -> u32 {
+    320
+}
+
+/// Which frames of the input end up on the sheet
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum Sampling {
+    #[default]
+    Even,
+    // First frames past the threshold, so a busy clip fills the sheet early
+    Scenes {
+        #[serde(default = "default_threshold")]
+        threshold: f64,
+    },
+}
+
+impl fmt::Display for Sampling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sampling::Even => write!(f, "even"),
+            Sampling::Scenes { threshold } => write!(f, "scenes{}", threshold),
+        }
+    }
+}
+
+/// Contact sheet options received from the GUI
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Sheet {
+    #[serde(default = "default_count")]
+    pub count: usize,
+    #[serde(default)]
+    pub sampling: Sampling,
+    // Width of every frame on the sheet
+    #[serde(default = "default_width")]
+    pub width: u32,
+    #[serde(default)]
+    pub timestamps: bool,
+    #[serde(default)]
+    pub format: ImageFormat,
+}
+
+impl Sheet {
+    /// Every option that changes the rendered sheet, so cached sheets are never reused for others
+    fn key(&self) -> String {
+        let overlay = match self.timestamps {
+            true => "timestamps",
+            false => "plain",
+        };
+        format!(
+            "{}-{}-{}w-{}",
+            self.count, self.sampling, self.width, overlay
+        )
+    }
+}
+
+impl Default for Sheet {
+    fn default() -> Sheet {
+        Sheet {
+            count: default_count(),
+            sampling: Sampling::default(),
+            width: default_width(),
+            timestamps: false,
+            format: ImageFormat::default(),
+        }
+    }
+}
+
+/// Wrapper for rendering a storyboard image of a single input with FFmpeg
+pub struct Storyboard {
+    input: String,
+    duration: f64,
+    sheet: Sheet,
+    output: PathBuf,
+    collision: Collision,
+    ffmpeg: Command,
+}
+
+impl Storyboard {
+    /// Writes to the `destination` the user picked when given, otherwise to a file named
+    /// after the input identity and the sheet options in `cache_dir`
+    pub fn new(
+        input: &str,
+        duration: f64,
+        sheet: Sheet,
+        destination: Option<&Destination>,
+        cache_dir: &Path,
+    ) -> Result<Storyboard, String> {
+        let mut storyboard = Storyboard {
+            input: input.into(),
+            duration,
+            sheet,
+            output: PathBuf::new(),
+            // Cache files are ours to replace
+            collision: Collision::Overwrite,
+            ffmpeg: Command::new("ffmpeg"),
+        };
+
+        storyboard.output = match destination {
+            Some(destination) => {
+                let (cols, rows) = storyboard.layout();
+                let fields = Fields {
+                    timestamp: Local::now(),
+                    layout: format!("{}x{}", cols, rows),
+                    count: sheet.count,
+                    stem: Path::new(input)
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                };
+                storyboard.collision = destination.collision;
+                destination
+                    .resolve(&fields, sheet.format.extension())
+                    .map_err(|e| e.to_string())?
+            }
+            None => {
+                let key =
+                    FileKey::new(input).map_err(|e| format!("Failed reading input: {}", e))?;
+                let name = format!(
+                    "{}-storyboard-{}.{}",
+                    key.digest(),
+                    sheet.key(),
+                    sheet.format.extension()
+                );
+                cache_dir.join(name)
+            }
+        };
+
+        Ok(storyboard)
+    }
+
+    pub fn output_path(&self) -> String {
+        self.output.to_string_lossy().into_owned()
+    }
+
+    /// Columns and rows of the sheet, laid out like an X stack of the same count
+    fn layout(&self) -> (usize, usize) {
+        let count = self.sheet.count.max(1);
+        let cols = columns(count);
+        (cols, count.div_ceil(cols))
+    }
+
+    fn chain(&self) -> Chain {
+        let sample = match self.sheet.sampling {
+            Sampling::Even => Filter::new("fps").option(
+                "fps",
+                format!("{}/{:.3}", self.sheet.count, self.duration.max(0.001)),
+            ),
+            Sampling::Scenes { threshold } => {
+                Filter::new("select").option("expr", format!("gt(scene,{})", threshold))
+            }
+        };
+
+        let mut chain = Chain::new().filter(sample).filter(
+            Filter::new("scale")
+                .option("w", self.sheet.width)
+                .option("h", -2),
+        );
+
+        if self.sheet.timestamps {
+            chain = chain.filter(
+                Filter::new("drawtext")
+                    .option("text", "%{pts:hms}")
+                    .option("x", PADDING * 2)
+                    .option("y", format!("h-th-{}", PADDING * 2))
+                    .option("fontcolor", "white")
+                    .option("box", 1)
+                    .option("boxcolor", "black@0.6")
+                    .option("boxborderw", PADDING),
+            );
+        }
+
+        let (cols, rows) = self.layout();
+        chain.filter(
+            Filter::new("tile")
+                .option("layout", format!("{}x{}", cols, rows))
+                .option("padding", PADDING)
+                .option("margin", PADDING),
+        )
+    }
+}
+
+impl Execution for Storyboard {
+    // FFmpeg pipes output to stderr
+    const HANDLE: Handle = Handle::Err;
+
+    fn assemble(&mut self) -> &mut Command {
+        let chain = self.chain();
+
+        self.ffmpeg
+            .arg(self.collision.flag())
+            .args(["-i", &self.input])
+            .args(["-vf", &chain.to_string()])
+            .args(["-frames:v", "1"])
+            .args(["-q:v", "3"])
+            .arg(&self.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::TempDir;
+    use super::*;
+    use std::ffi::OsStr;
+    use std::fs;
+
+    fn storyboard(sheet: Sheet) -> Storyboard {
+        Storyboard {
+            input: String::from("videos/1.mov"),
+            duration: 60.0,
+            sheet,
+            output: PathBuf::from("sheet.jpg"),
+            collision: Collision::Overwrite,
+            ffmpeg: Command::new("ffmpeg"),
+        }
+    }
+
+    #[test]
+    fn it_assembles() {
+        let mut storyboard = storyboard(Sheet {
+            timestamps: true,
+            ..Default::default()
+        });
+        let result: Vec<&OsStr> = storyboard.assemble().get_args().collect();
+
+        assert_eq!(
+            result,
+            [
+                "-y",
+                "-i",
+                "videos/1.mov",
+                "-vf",
+                "fps=fps=12/60.000,scale=w=320:h=-2,\
+                 drawtext=text=%{pts\\\\:hms}:x=8:y=h-th-8:fontcolor=white:\
+                 box=1:boxcolor=black@0.6:boxborderw=4,\
+                 tile=layout=3x4:padding=4:margin=4",
+                "-frames:v",
+                "1",
+                "-q:v",
+                "3",
+                "sheet.jpg"
+            ]
+        )
+    }
+
+    #[test]
+    fn it_keys_every_option() {
+        let sheet = Sheet::default();
+        assert_eq!(sheet.key(), "12-even-320w-plain");
+
+        let variants = [
+            Sheet {
+                sampling: Sampling::Scenes { threshold: 0.4 },
+                ..sheet
+            },
+            Sheet {
+                width: 480,
+                ..sheet
+            },
+            Sheet {
+                timestamps: true,
+                ..sheet
+            },
+            Sheet { count: 6, ..sheet },
+        ];
+        for variant in variants {
+            assert_ne!(variant.key(), sheet.key());
+        }
+    }
+
+    #[test]
+    fn it_keeps_chosen_outputs() {
+        let dir = TempDir::new("storyboarding");
+        let taken = dir.join("board.jpg");
+        fs::write(&taken, "").unwrap();
+
+        let destination = |collision: Collision| Destination {
+            path: taken.to_string_lossy().into_owned(),
+            collision,
+            ..Default::default()
+        };
+        let chosen = |collision: Collision| {
+            Storyboard::new(
+                "videos/1.mov",
+                60.0,
+                Sheet::default(),
+                Some(&destination(collision)),
+                &dir,
+            )
+        };
+
+        let mut kept = chosen(Collision::Increment).unwrap();
+        assert_eq!(kept.output, dir.join("board-2.jpg"));
+        assert_eq!(kept.assemble().get_args().next(), Some(OsStr::new("-n")));
+
+        let mut replaced = chosen(Collision::Overwrite).unwrap();
+        assert_eq!(replaced.output, taken);
+        assert_eq!(
+            replaced.assemble().get_args().next(),
+            Some(OsStr::new("-y"))
+        );
+
+        assert!(chosen(Collision::Fail).is_err());
+    }
+
+    #[test]
+    fn it_samples_scenes() {
+        let storyboard = storyboard(Sheet {
+            count: 5,
+            sampling: Sampling::Scenes { threshold: 0.4 },
+            ..Default::default()
+        });
+
+        assert_eq!(storyboard.layout(), (2, 3));
+        assert!(storyboard
+            .chain()
+            .to_string()
+            .starts_with("select=expr=gt(scene\\,0.4),scale"));
+    }
+}
@@ -0,0 +1,320 @@
+use super::tail;
+
+use core::fmt;
+use log::{debug, error};
+use serde::Deserialize;
+use std::io::{self, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// How long FFmpeg gets to close the stream after being asked to quit
+const STOP_GRACE: Duration = Duration::from_secs(3);
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+// Lines of FFmpeg's log kept to explain why a stream ended
+const LOG_TAIL: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    Srt,
+    Udp,
+    Rtmp,
+}
+
+impl Protocol {
+    /// Container FFmpeg muxes into before handing packets to the protocol
+    pub fn muxer(&self) -> &'static str {
+        match self {
+            Protocol::Srt | Protocol::Udp => "mpegts",
+            Protocol::Rtmp => "flv",
+        }
+    }
+}
+
+/// Network endpoint the stack is pushed to instead of a file, e.g. `srt://192.168.1.20:9000`
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "String")]
+pub struct Target {
+    pub url: String,
+    pub protocol: Protocol,
+}
+
+impl TryFrom<String> for Target {
+    type Error = String;
+
+    fn try_from(url: String) -> Result<Target, String> {
+        let (scheme, _) = url
+            .split_once("://")
+            .ok_or(format!("Stream target {} has no scheme", url))?;
+
+        let protocol = match scheme.to_lowercase().as_str() {
+            "srt" => Protocol::Srt,
+            "udp" => Protocol::Udp,
+            "rtmp" => Protocol::Rtmp,
+            _ => return Err(format!("Unsupported stream protocol: {}", scheme)),
+        };
+
+        Ok(Target { url, protocol })
+    }
+}
+
+impl Target {
+    /// Encoding and muxer arguments ending with the target URL
+    pub fn args(&self) -> Vec<String> {
+        [
+            "-c:v",
+            "libx264",
+            "-preset",
+            "veryfast",
+            "-tune",
+            "zerolatency",
+            "-pix_fmt",
+            "yuv420p",
+            "-c:a",
+            "aac",
+            "-f",
+            self.protocol.muxer(),
+            &self.url,
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.url)
+    }
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Running,
+    Spawn(io::Error),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::Running => write!(f, "A stream is already running"),
+            SessionError::Spawn(e) => write!(f, "Failed starting stream: {}", e),
+        }
+    }
+}
+
+struct Running {
+    generation: u64,
+    child: Child,
+    log: JoinHandle<Vec<String>>,
+}
+
+/// Stream that exited on its own, with the end of its log
+pub struct Ended {
+    pub status: ExitStatus,
+    pub log: Vec<String>,
+}
+
+/// The one live stream the app may run, kept in app state so it can be stopped later
+#[derive(Default)]
+pub struct Session {
+    running: Mutex<Option<Running>>,
+    // Counts started streams, so a watcher can tell its stream from a restarted one
+    started: AtomicU64,
+}
+
+impl Session {
+    pub fn new() -> Session {
+        Session::default()
+    }
+
+    /// Spawn the command without waiting on it, returning the generation of the new stream.
+    /// The log is drained in the background and its tail kept for when the stream ends.
+    pub fn start(&self, command: &mut Command) -> Result<u64, SessionError> {
+        let mut running = self.running.lock().unwrap();
+
+        if running.is_some() && self.reap(&mut running).is_none() {
+            return Err(SessionError::Running);
+        }
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(SessionError::Spawn)?;
+
+        let generation = self.started.fetch_add(1, Ordering::Relaxed) + 1;
+        debug!("Started stream {} with pid {}", generation, child.id());
+        let stderr = child.stderr.take().expect("stderr is piped");
+        *running = Some(Running {
+            generation,
+            child,
+            log: tail(stderr, LOG_TAIL),
+        });
+        Ok(generation)
+    }
+
+    // Clears the slot once the process has exited, returning how it ended
+    fn reap(&self, running: &mut Option<Running>) -> Option<Ended> {
+        let status = running.as_mut()?.child.try_wait().ok().flatten()?;
+        let log = running.take()?.log.join().unwrap_or_default();
+        Some(Ended { status, log })
+    }
+
+    /// How stream `generation` exited if it ended on its own.
+    /// `None` while it runs, once it was stopped or when another stream took its place.
+    pub fn finished(&self, generation: u64) -> Option<Ended> {
+        let mut running = self.running.lock().unwrap();
+
+        match running.as_ref() {
+            Some(current) if current.generation == generation => self.reap(&mut running),
+            _ => None,
+        }
+    }
+
+    /// Whether stream `generation` still holds the session, exited or not
+    pub fn holds(&self, generation: u64) -> bool {
+        let running = self.running.lock().unwrap();
+        running
+            .as_ref()
+            .is_some_and(|current| current.generation == generation)
+    }
+
+    /// Ask FFmpeg to quit so it can close the stream cleanly, killing it if it doesn't.
+    /// Returns false when nothing was streaming.
+    pub fn stop(&self) -> bool {
+        let Some(Running { mut child, .. }) = self.running.lock().unwrap().take() else {
+            return false;
+        };
+
+        // FFmpeg quits on `q` from stdin
+        if let Some(stdin) = child.stdin.as_mut() {
+            let _ = stdin.write_all(b"q");
+        }
+        drop(child.stdin.take());
+
+        let started = Instant::now();
+        while started.elapsed() < STOP_GRACE {
+            if let Ok(Some(_)) = child.try_wait() {
+                return true;
+            }
+            thread::sleep(STOP_POLL);
+        }
+
+        if let Err(e) = child.kill().and_then(|_| child.wait().map(|_| ())) {
+            error!("Failed killing stream: {e}");
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_targets() {
+        let target = Target::try_from(String::from("srt://127.0.0.1:9000?mode=caller")).unwrap();
+        assert_eq!(target.protocol.muxer(), "mpegts");
+
+        let target = Target::try_from(String::from("rtmp://localhost/live/review")).unwrap();
+        assert_eq!(
+            target.args()[10..],
+            ["-f", "flv", "rtmp://localhost/live/review"]
+        );
+
+        assert!(Target::try_from(String::from("http://localhost")).is_err());
+        assert!(Target::try_from(String::from("localhost:9000")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_stops_sessions() {
+        let session = Session::new();
+        assert!(!session.stop());
+
+        // Stands in for FFmpeg, exits once it reads the quit key
+        let mut command = Command::new("head");
+        command.args(["-c", "1"]);
+
+        let generation = session.start(&mut command).unwrap();
+        assert!(session.holds(generation));
+        assert!(matches!(
+            session.start(&mut command),
+            Err(SessionError::Running)
+        ));
+
+        assert!(session.stop());
+        assert!(!session.holds(generation));
+        assert!(session.finished(generation).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_tells_restarted_streams_apart() {
+        let session = Session::new();
+        let mut command = Command::new("head");
+        command.args(["-c", "1"]);
+
+        let first = session.start(&mut command).unwrap();
+        session.stop();
+        let second = session.start(&mut command).unwrap();
+
+        assert_ne!(first, second);
+        assert!(!session.holds(first));
+        assert!(session.holds(second));
+        assert!(session.finished(first).is_none());
+        session.stop();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_keeps_the_log_tail() {
+        let session = Session::new();
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo starting >&2; echo refused >&2; exit 1"]);
+        let generation = session.start(&mut command).unwrap();
+
+        let ended = loop {
+            if let Some(ended) = session.finished(generation) {
+                break ended;
+            }
+            thread::sleep(STOP_POLL);
+        };
+        assert!(!ended.status.success());
+        assert_eq!(ended.log, ["starting", "refused"]);
+    }
+
+    // Needs FFmpeg on the path, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn it_streams_to_udp() {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        let url = format!("udp://{}?pkt_size=1316", socket.local_addr().unwrap());
+        let target = Target::try_from(url).unwrap();
+
+        let mut command = Command::new("ffmpeg");
+        command
+            .args(["-re", "-f", "lavfi", "-i", "testsrc=size=64x64:rate=10"])
+            .args(["-t", "5"])
+            .args(target.args());
+
+        let session = Session::new();
+        session.start(&mut command).unwrap();
+
+        let mut packet = [0; 1316];
+        let received = socket.recv(&mut packet);
+        session.stop();
+
+        // MPEG-TS packets start with a sync byte
+        assert!(received.unwrap() > 0);
+        assert_eq!(packet[0], 0x47);
+    }
+}
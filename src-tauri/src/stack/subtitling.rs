@@ -0,0 +1,206 @@
+use super::filtering::Filter;
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Where the subtitles burned into a tile or the mosaic come from
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "source", rename_all = "lowercase")]
+pub enum SubtitleSource {
+    // Counted among the subtitle streams of the input only, `0` is its first one
+    Embedded { index: usize },
+    // Sidecar SRT or ASS file timed like the input
+    File { path: String },
+}
+
+/// Overrides applied on top of the subtitle's own styling
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct SubtitleStyle {
+    #[serde(default)]
+    pub font: Option<String>,
+    #[serde(default)]
+    pub size: Option<u16>,
+    // Hex in `#RRGGBB` form
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub outline: Option<f64>,
+    // Distance from the bottom edge in pixels
+    #[serde(default)]
+    pub margin: Option<u16>,
+}
+
+impl SubtitleStyle {
+    /// ASS colour of a `#RRGGBB` hex, which orders the channels blue first
+    fn colour(hex: &str) -> Option<String> {
+        let hex = hex.strip_prefix('#')?;
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let (r, g, b) = (&hex[0..2], &hex[2..4], &hex[4..6]);
+        Some(format!("&H00{}{}{}", b, g, r).to_uppercase())
+    }
+
+    /// Value for the `force_style` option, `None` without overrides
+    fn force_style(&self) -> Option<String> {
+        let fields = [
+            self.font.as_ref().map(|font| format!("FontName={}", font)),
+            self.size.map(|size| format!("FontSize={}", size)),
+            self.color
+                .as_deref()
+                .and_then(SubtitleStyle::colour)
+                .map(|colour| format!("PrimaryColour={}", colour)),
+            self.outline.map(|outline| format!("Outline={}", outline)),
+            self.margin.map(|margin| format!("MarginV={}", margin)),
+        ];
+
+        let style: Vec<String> = fields.into_iter().flatten().collect();
+        (!style.is_empty()).then(|| style.join(","))
+    }
+}
+
+/// Subtitles received from the GUI, for a single tile or the whole mosaic
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Subtitles {
+    #[serde(flatten)]
+    pub source: SubtitleSource,
+    #[serde(default)]
+    pub style: SubtitleStyle,
+}
+
+impl Subtitles {
+    pub fn validate(&self) -> Result<(), String> {
+        if let SubtitleSource::File { path } = &self.source {
+            if !Path::new(path).is_file() {
+                return Err(format!("Subtitle file {} doesn't exist", path));
+            }
+        }
+
+        if let Some(color) = &self.style.color {
+            SubtitleStyle::colour(color)
+                .ok_or(format!("Subtitle color {} isn't in #RRGGBB form", color))?;
+        }
+
+        Ok(())
+    }
+
+    /// Filters burning the subtitles into frames of `input` trimmed to begin at `start` and
+    /// played at `speed`. Input seeking restarts timestamps at zero and speed changes rescale
+    /// them, so they're mapped back to source time for the subtitles and restored after.
+    pub fn filters(&self, input: &str, start: Option<u32>, speed: Option<f64>) -> Vec<Filter> {
+        let subtitles = match &self.source {
+            SubtitleSource::Embedded { index } => Filter::new("subtitles")
+                .option("filename", input)
+                .option("si", index),
+            SubtitleSource::File { path } => Filter::new("subtitles").option("filename", path),
+        };
+        let subtitles = match self.style.force_style() {
+            Some(style) => subtitles.option("force_style", style),
+            None => subtitles,
+        };
+
+        let (start, speed) = (start.unwrap_or_default(), speed.unwrap_or(1.0));
+        let (source, restore) = match (start > 0, speed != 1.0) {
+            (false, false) => return vec![subtitles],
+            (true, false) => (format!("PTS+{}/TB", start), String::from("PTS-STARTPTS")),
+            (false, true) => (
+                format!("PTS*{}", speed),
+                format!("(PTS-STARTPTS)/{}", speed),
+            ),
+            (true, true) => (
+                format!("PTS*{}+{}/TB", speed, start),
+                format!("(PTS-STARTPTS)/{}", speed),
+            ),
+        };
+
+        vec![
+            Filter::new("setpts").option("expr", source),
+            subtitles,
+            Filter::new("setpts").option("expr", restore),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_burns_subtitles() {
+        let subtitles = Subtitles {
+            source: SubtitleSource::File {
+                path: String::from(r"C:\subs\it's.srt"),
+            },
+            style: SubtitleStyle {
+                size: Some(24),
+                color: Some(String::from("#ffcc00")),
+                ..Default::default()
+            },
+        };
+
+        let result: Vec<String> = subtitles
+            .filters("1.mov", None, None)
+            .iter()
+            .map(Filter::to_string)
+            .collect();
+        assert_eq!(
+            result,
+            [
+                r"subtitles=filename=C\\:\\\\subs\\\\it\\\'s.srt:force_style=FontSize=24\,PrimaryColour=&H0000CCFF"
+            ]
+        );
+
+        let embedded = Subtitles {
+            source: SubtitleSource::Embedded { index: 1 },
+            style: SubtitleStyle::default(),
+        };
+        let result: Vec<String> = embedded
+            .filters("videos/1.mkv", Some(30), None)
+            .iter()
+            .map(Filter::to_string)
+            .collect();
+        assert_eq!(
+            result,
+            [
+                "setpts=expr=PTS+30/TB",
+                "subtitles=filename=videos/1.mkv:si=1",
+                "setpts=expr=PTS-STARTPTS"
+            ]
+        );
+
+        let result: Vec<String> = embedded
+            .filters("videos/1.mkv", Some(30), Some(2.0))
+            .iter()
+            .map(Filter::to_string)
+            .collect();
+        assert_eq!(
+            result,
+            [
+                "setpts=expr=PTS*2+30/TB",
+                "subtitles=filename=videos/1.mkv:si=1",
+                "setpts=expr=(PTS-STARTPTS)/2"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_validates_style() {
+        let subtitles = Subtitles {
+            source: SubtitleSource::Embedded { index: 0 },
+            style: SubtitleStyle {
+                color: Some(String::from("yellow")),
+                ..Default::default()
+            },
+        };
+        assert!(subtitles.validate().is_err());
+
+        let missing = Subtitles {
+            source: SubtitleSource::File {
+                path: String::from("missing.srt"),
+            },
+            style: SubtitleStyle::default(),
+        };
+        assert!(missing.validate().is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use super::priming::{Duration, Primed};
+
+use chrono::DateTime;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Key of the tag listing the inputs of a stack
+pub const SOURCES_TAG: &str = "stacked_sources";
+
+fn default_sources() -> bool {
+    true
+}
+
+/// Descriptive tags written into the output file
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Metadata {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    // RFC 3339 timestamp or `now`, which FFmpeg fills in when muxing
+    #[serde(default)]
+    pub creation_time: Option<String>,
+    #[serde(default)]
+    pub video_title: Option<String>,
+    #[serde(default)]
+    pub audio_title: Option<String>,
+    // Lists every input with its grid position and trim
+    #[serde(default = "default_sources")]
+    pub sources: bool,
+}
+
+impl Default for Metadata {
+    fn default() -> Metadata {
+        Metadata {
+            title: None,
+            comment: None,
+            creation_time: None,
+            video_title: None,
+            audio_title: None,
+            sources: default_sources(),
+        }
+    }
+}
+
+impl Metadata {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.creation_time.as_deref() {
+            None | Some("now") => Ok(()),
+            Some(time) => DateTime::parse_from_rfc3339(time)
+                .map(|_| ())
+                .map_err(|e| format!("Creation time {} isn't a valid timestamp: {}", time, e)),
+        }
+    }
+
+    /// Metadata arguments for an output of the `primed` tiles, with an audio stream when `audio`
+    pub fn args(&self, primed: &[Primed], audio: bool) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut tag = |option: &str, key: &str, value: &str| {
+            args.extend([String::from(option), format!("{}={}", key, value)]);
+        };
+
+        let global = [
+            ("title", &self.title),
+            ("comment", &self.comment),
+            ("creation_time", &self.creation_time),
+        ];
+        for (key, value) in global {
+            if let Some(value) = value {
+                tag("-metadata", key, value);
+            }
+        }
+
+        if let Some(title) = &self.video_title {
+            tag("-metadata:s:v:0", "title", title);
+        }
+        if let (Some(title), true) = (&self.audio_title, audio) {
+            tag("-metadata:s:a:0", "title", title);
+        }
+
+        if self.sources {
+            tag("-metadata", SOURCES_TAG, &describe(primed));
+        }
+
+        args
+    }
+}
+
+/// Inputs in stack order, e.g. `a.mov (0,0) 00:00:05-00:00:15; b.mov (1,0)`
+pub fn describe(primed: &[Primed]) -> String {
+    primed
+        .iter()
+        .map(|prime| {
+            let name = Path::new(&prime.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or(prime.path.clone());
+            let source = format!("{} ({},{})", name, prime.x, prime.y);
+
+            match (prime.start, prime.end) {
+                (Some(start), Some(end)) => {
+                    format!("{} {}-{}", source, start.as_ts(), end.as_ts())
+                }
+                _ => source,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_tags_outputs() {
+        let primed = vec![
+            Primed {
+                path: String::from("videos/a.mov"),
+                start: Some(5),
+                end: Some(15),
+                ..Default::default()
+            },
+            Primed {
+                x: 1,
+                path: String::from("videos/b.mov"),
+                ..Default::default()
+            },
+        ];
+        let metadata = Metadata {
+            title: Some(String::from("Review")),
+            audio_title: Some(String::from("Director")),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            metadata.args(&primed, false),
+            [
+                "-metadata",
+                "title=Review",
+                "-metadata",
+                "stacked_sources=a.mov (0,0) 00:00:05-00:00:15; b.mov (1,0)"
+            ]
+        );
+
+        assert!(metadata.validate().is_ok());
+        let metadata = Metadata {
+            creation_time: Some(String::from("yesterday")),
+            ..metadata
+        };
+        assert!(metadata.validate().is_err());
+    }
+}
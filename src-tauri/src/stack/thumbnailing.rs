@@ -0,0 +1,222 @@
+use super::caching::FileKey;
+use super::filtering::{Chain, Filter};
+use super::{Execution, Handle};
+
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Image format used for cached thumbnails
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    #[default]
+    Jpeg,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+/// Timestamps for `n` frames spread evenly over the duration, avoiding the very first and last frame
+pub fn spaced(duration: f64, n: usize) -> Vec<f64> {
+    let step = duration / n as f64;
+    (0..n).map(|idx| (idx as f64 + 0.5) * step).collect()
+}
+
+/// Wrapper for extracting a single frame, or a strip of frames tiled in one row, with FFmpeg
+pub struct Thumbnail {
+    input: String,
+    timestamp: f64,
+    // Seconds between the frames of a strip
+    interval: f64,
+    count: usize,
+    width: u32,
+    output: PathBuf,
+    ffmpeg: Command,
+}
+
+impl Thumbnail {
+    /// Thumbnails are named after the file identity so edits to the input invalidate them
+    pub fn new(
+        input: &str,
+        timestamp: f64,
+        width: u32,
+        format: ImageFormat,
+        cache_dir: &Path,
+    ) -> io::Result<Thumbnail> {
+        let key = FileKey::new(input)?;
+        let name = format!(
+            "{}-{}-{}.{}",
+            key.digest(),
+            (timestamp * 1000.0) as u64,
+            width,
+            format.extension()
+        );
+
+        Ok(Thumbnail {
+            input: input.into(),
+            timestamp,
+            interval: 0.0,
+            count: 1,
+            width,
+            output: cache_dir.join(name),
+            ffmpeg: Command::new("ffmpeg"),
+        })
+    }
+
+    /// `count` evenly spaced frames side by side, each `width` pixels wide
+    pub fn strip(
+        input: &str,
+        duration: f64,
+        count: usize,
+        width: u32,
+        format: ImageFormat,
+        cache_dir: &Path,
+    ) -> io::Result<Thumbnail> {
+        let count = count.max(1);
+        let key = FileKey::new(input)?;
+        let name = format!(
+            "{}-strip{}-{}.{}",
+            key.digest(),
+            count,
+            width,
+            format.extension()
+        );
+        Ok(Thumbnail {
+            input: input.into(),
+            timestamp: spaced(duration, count)[0],
+            interval: duration / count as f64,
+            count,
+            width,
+            output: cache_dir.join(name),
+            ffmpeg: Command::new("ffmpeg"),
+        })
+    }
+
+    pub fn output_path(&self) -> String {
+        self.output.to_string_lossy().into_owned()
+    }
+
+    /// Where FFmpeg writes until the frame is complete, keeping the image extension
+    fn partial_path(&self) -> PathBuf {
+        let name = self
+            .output
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        self.output.with_file_name(format!("part-{}", name))
+    }
+
+    pub fn is_cached(&self) -> bool {
+        self.output.exists()
+    }
+
+    /// Render under a temporary name and move it into place once FFmpeg succeeds,
+    /// so an interrupted run never leaves a truncated image that looks cached
+    pub fn extract(&mut self) -> Result<(), String> {
+        let partial = self.partial_path();
+
+        if let Err(e) = self.execute() {
+            let _ = fs::remove_file(&partial);
+            return Err(format!("Failed extracting frame: {:#?}", e));
+        }
+
+        fs::rename(&partial, &self.output).map_err(|e| format!("Failed caching frame: {}", e))
+    }
+}
+
+impl Execution for Thumbnail {
+    // FFmpeg pipes output to stderr
+    const HANDLE: Handle = Handle::Err;
+
+    fn assemble(&mut self) -> &mut Command {
+        let mut chain = Chain::new();
+        if self.count > 1 {
+            // Seeking lands on the first frame, the rest follow one interval apart
+            chain = chain.filter(Filter::new("fps").option("fps", format!("1/{}", self.interval)));
+        }
+        chain = chain.filter(Filter::new("scale").option("w", self.width).option("h", -2));
+        if self.count > 1 {
+            chain = chain.filter(Filter::new("tile").option("layout", format!("{}x1", self.count)));
+        }
+
+        let partial = self.partial_path();
+
+        // Seeking before the input is fast and accurate enough for a preview
+        self.ffmpeg
+            .arg("-y")
+            .args(["-ss", &format!("{:.3}", self.timestamp)])
+            .args(["-i", &self.input])
+            .args(["-frames:v", "1"])
+            .args(["-vf", &chain.to_string()])
+            .args(["-q:v", "3"])
+            .arg(partial)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn it_spaces_timestamps() {
+        assert_eq!(spaced(10.0, 1), [5.0]);
+        assert_eq!(spaced(10.0, 4), [1.25, 3.75, 6.25, 8.75]);
+    }
+
+    #[test]
+    fn it_assembles() {
+        let mut thumbnail = Thumbnail {
+            input: String::from("videos/1.mov"),
+            timestamp: 2.5,
+            interval: 0.0,
+            count: 1,
+            width: 320,
+            output: PathBuf::from("cache/1.jpg"),
+            ffmpeg: Command::new("ffmpeg"),
+        };
+        let result: Vec<&OsStr> = thumbnail.assemble().get_args().collect();
+
+        assert_eq!(
+            result,
+            [
+                "-y",
+                "-ss",
+                "2.500",
+                "-i",
+                "videos/1.mov",
+                "-frames:v",
+                "1",
+                "-vf",
+                "scale=w=320:h=-2",
+                "-q:v",
+                "3",
+                "cache/part-1.jpg"
+            ]
+        );
+
+        let mut strip = Thumbnail {
+            input: String::from("videos/1.mov"),
+            timestamp: 1.25,
+            interval: 2.5,
+            count: 4,
+            width: 160,
+            output: PathBuf::from("cache/strip.jpg"),
+            ffmpeg: Command::new("ffmpeg"),
+        };
+        let result: Vec<&OsStr> = strip.assemble().get_args().collect();
+
+        assert_eq!(result[2], "1.250");
+        assert_eq!(result[8], "fps=fps=1/2.5,scale=w=160:h=-2,tile=layout=4x1");
+    }
+}
@@ -0,0 +1,112 @@
+use super::filtering::{Chain, Filter, Pad};
+
+use serde::{Deserialize, Serialize};
+
+// Frame rate of rendered visualizations
+const RATE: u8 = 30;
+
+/// How an audio-only input is drawn as a tile
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Visualization {
+    #[default]
+    Waves,
+    Spectrum,
+    Vectorscope,
+    // EBU R128 loudness meter
+    Loudness,
+}
+
+impl Visualization {
+    /// Chains rendering the audio at `input` as video of the given size at `output`.
+    /// Every visualization ends in yuv420p so it stacks with regular video tiles.
+    pub fn chains(
+        &self,
+        idx: usize,
+        input: Pad,
+        width: u16,
+        height: u16,
+        output: Pad,
+    ) -> Vec<Chain> {
+        let size = format!("{}x{}", width, height);
+        let format = Filter::new("format").option("pix_fmts", "yuv420p");
+        let chain = Chain::new().inputs(vec![input]);
+
+        let chain = match self {
+            Visualization::Waves => chain.filter(
+                Filter::new("showwaves")
+                    .option("s", size)
+                    .option("mode", "cline")
+                    .option("rate", RATE),
+            ),
+            Visualization::Spectrum => chain.filter(
+                Filter::new("showspectrum")
+                    .option("s", size)
+                    .option("slide", "scroll")
+                    .option("fps", RATE),
+            ),
+            Visualization::Vectorscope => chain
+                .filter(Filter::new("aformat").option("channel_layouts", "stereo"))
+                .filter(
+                    Filter::new("avectorscope")
+                        .option("s", size)
+                        .option("rate", RATE),
+                ),
+            Visualization::Loudness => {
+                // The meter also passes the audio through, which has to end somewhere
+                let (meter, passthrough) = (
+                    Pad::named(&format!("m{}", idx)),
+                    Pad::named(&format!("p{}", idx)),
+                );
+
+                return vec![
+                    chain
+                        .filter(
+                            Filter::new("ebur128")
+                                .option("video", 1)
+                                .option("meter", 18),
+                        )
+                        .output(meter.clone())
+                        .output(passthrough.clone()),
+                    Chain::new()
+                        .inputs(vec![passthrough])
+                        .filter(Filter::new("anullsink")),
+                    Chain::new()
+                        .inputs(vec![meter])
+                        .filter(Filter::new("scale").option("w", width).option("h", height))
+                        .filter(format)
+                        .output(output),
+                ];
+            }
+        };
+
+        vec![chain.filter(format).output(output)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_visualizations() {
+        let chains =
+            Visualization::Waves.chains(0, Pad::stream(0, "a:0"), 640, 360, Pad::named("w0"));
+        assert_eq!(
+            chains[0].to_string(),
+            "[0:a:0]showwaves=s=640x360:mode=cline:rate=30,format=pix_fmts=yuv420p[w0]"
+        );
+
+        let chains =
+            Visualization::Loudness.chains(1, Pad::stream(1, "a:0"), 640, 360, Pad::named("w1"));
+        let result: Vec<String> = chains.iter().map(Chain::to_string).collect();
+        assert_eq!(
+            result,
+            [
+                "[1:a:0]ebur128=video=1:meter=18[m1][p1]",
+                "[p1]anullsink",
+                "[m1]scale=w=640:h=360,format=pix_fmts=yuv420p[w1]"
+            ]
+        );
+    }
+}
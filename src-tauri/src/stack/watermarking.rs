@@ -0,0 +1,127 @@
+use super::filtering::{Chain, Filter, Pad};
+
+use serde::Deserialize;
+use std::path::Path;
+
+fn default_opacity() -> f64 {
+    1.0
+}
+
+fn default_margin() -> u32 {
+    16
+}
+
+/// Where the watermark sits on the mosaic, a corner like `"top-right"` or
+/// `{"coordinates": {"x": 40, "y": 20}}`
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Placement {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    // Top left corner of the image in mosaic pixels, the margin is ignored
+    Coordinates {
+        x: u32,
+        y: u32,
+    },
+}
+
+/// Image laid over the whole stacked output, e.g. a PNG logo with alpha
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Watermark {
+    pub path: String,
+    #[serde(default)]
+    pub placement: Placement,
+    // Width as a fraction of the mosaic width, the image's own size when not set
+    #[serde(default)]
+    pub scale: Option<f64>,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    // Distance from the edges for corner placements
+    #[serde(default = "default_margin")]
+    pub margin: u32,
+}
+
+impl Watermark {
+    pub fn validate(&self) -> Result<(), String> {
+        if !Path::new(&self.path).is_file() {
+            return Err(format!("Watermark image {} doesn't exist", self.path));
+        }
+
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(format!(
+                "Watermark opacity {} is outside 0 to 1",
+                self.opacity
+            ));
+        }
+
+        match self.scale {
+            Some(scale) if scale <= 0.0 || scale > 1.0 => Err(format!(
+                "Watermark scale {} must be above 0 and at most 1",
+                scale
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Chain preparing the image at `input` for a mosaic `width` pixels wide
+    pub fn chain(&self, input: Pad, width: u32, output: Pad) -> Chain {
+        let mut chain = Chain::new()
+            .inputs(vec![input])
+            .filter(Filter::new("format").option("pix_fmts", "rgba"));
+
+        if let Some(scale) = self.scale {
+            let width = ((width as f64 * scale).round() as u32).max(1);
+            chain = chain.filter(Filter::new("scale").option("w", width).option("h", -1));
+        }
+
+        if self.opacity < 1.0 {
+            chain = chain.filter(Filter::new("colorchannelmixer").option("aa", self.opacity));
+        }
+
+        chain.output(output)
+    }
+
+    /// Overlay taking the mosaic and the prepared image, in that order
+    pub fn overlay(&self) -> Filter {
+        let margin = self.margin;
+        let (x, y) = match self.placement {
+            Placement::TopLeft => (margin.to_string(), margin.to_string()),
+            Placement::TopRight => (format!("W-w-{}", margin), margin.to_string()),
+            Placement::BottomLeft => (margin.to_string(), format!("H-h-{}", margin)),
+            Placement::BottomRight => (format!("W-w-{}", margin), format!("H-h-{}", margin)),
+            Placement::Coordinates { x, y } => (x.to_string(), y.to_string()),
+        };
+
+        Filter::new("overlay").option("x", x).option("y", y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_places_watermarks() {
+        let watermark: Watermark = serde_json::from_str(
+            r#"{"path": "logo.png", "placement": "top-right", "scale": 0.1, "opacity": 0.5}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            watermark
+                .chain(Pad::stream(2, "v"), 3840, Pad::named("l"))
+                .to_string(),
+            "[2:v]format=pix_fmts=rgba,scale=w=384:h=-1,colorchannelmixer=aa=0.5[l]"
+        );
+        assert_eq!(watermark.overlay().to_string(), "overlay=x=W-w-16:y=16");
+
+        let watermark = Watermark {
+            placement: Placement::Coordinates { x: 40, y: 20 },
+            ..watermark
+        };
+        assert_eq!(watermark.overlay().to_string(), "overlay=x=40:y=20");
+    }
+}
@@ -0,0 +1,205 @@
+use super::caching::FileKey;
+use super::filtering::{Chain, Filter, Pad};
+use super::{Execution, Handle};
+
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Plenty of resolution for drawing, keeps decoded PCM small for long inputs
+const PEAK_SAMPLE_RATE: u32 = 4000;
+
+/// Whether the waveform is rendered by FFmpeg or returned as data for the GUI to draw
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WaveformMode {
+    #[default]
+    Image,
+    Peaks,
+}
+
+/// Wrapper for rendering the audio waveform of an input to a PNG with `showwavespic`
+pub struct Waveform {
+    input: String,
+    width: u32,
+    height: u32,
+    output: PathBuf,
+    ffmpeg: Command,
+}
+
+impl Waveform {
+    pub fn new(input: &str, width: u32, height: u32, cache_dir: &Path) -> io::Result<Waveform> {
+        let key = FileKey::new(input)?;
+        let name = format!("{}-wave-{}x{}.png", key.digest(), width, height);
+
+        Ok(Waveform {
+            input: input.into(),
+            width,
+            height,
+            output: cache_dir.join(name),
+            ffmpeg: Command::new("ffmpeg"),
+        })
+    }
+
+    pub fn output_path(&self) -> String {
+        self.output.to_string_lossy().into_owned()
+    }
+
+    fn partial_path(&self) -> PathBuf {
+        let name = self
+            .output
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        self.output.with_file_name(format!("part-{}", name))
+    }
+
+    pub fn is_cached(&self) -> bool {
+        self.output.exists()
+    }
+
+    /// Render under a temporary name and move it into place once FFmpeg succeeds,
+    /// so a failed run never leaves a broken image behind that looks cached
+    pub fn render(&mut self) -> Result<(), String> {
+        let partial = self.partial_path();
+
+        if let Err(e) = self.execute() {
+            let _ = fs::remove_file(&partial);
+            return Err(format!("Failed rendering waveform: {:#?}", e));
+        }
+
+        fs::rename(&partial, &self.output).map_err(|e| format!("Failed caching waveform: {}", e))
+    }
+}
+
+impl Execution for Waveform {
+    // FFmpeg pipes output to stderr
+    const HANDLE: Handle = Handle::Err;
+
+    fn assemble(&mut self) -> &mut Command {
+        let graph = Chain::new()
+            .inputs(vec![Pad::stream(0, "a:0")])
+            .filter(Filter::new("aformat").option("channel_layouts", "mono"))
+            .filter(
+                Filter::new("showwavespic")
+                    .option("s", format!("{}x{}", self.width, self.height))
+                    .option("colors", "#22b8cf"),
+            );
+
+        let partial = self.partial_path();
+
+        self.ffmpeg
+            .arg("-y")
+            .args(["-i", &self.input])
+            .args(["-filter_complex", &graph.to_string()])
+            .args(["-frames:v", "1"])
+            .arg(partial)
+    }
+}
+
+/// Wrapper for decoding the first audio stream of an input to raw mono PCM on stdout
+pub struct Samples {
+    input: String,
+    ffmpeg: Command,
+}
+
+impl Samples {
+    pub fn new(input: &str) -> Samples {
+        Samples {
+            input: input.into(),
+            ffmpeg: Command::new("ffmpeg"),
+        }
+    }
+}
+
+impl Execution for Samples {
+    // Decoded samples are piped to stdout
+    const HANDLE: Handle = Handle::Out;
+
+    fn assemble(&mut self) -> &mut Command {
+        self.ffmpeg
+            .args(["-v", "error"])
+            .args(["-i", &self.input])
+            .args(["-map", "0:a:0"])
+            .args(["-ac", "1"])
+            .args(["-ar", &PEAK_SAMPLE_RATE.to_string()])
+            .args(["-f", "s16le"])
+            .arg("-")
+    }
+}
+
+/// Downsample signed 16-bit PCM into `buckets` peak amplitudes between 0 and 1
+pub fn peaks(pcm: &[u8], buckets: usize) -> Vec<f32> {
+    let samples: Vec<i16> = pcm
+        .chunks_exact(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+
+    if samples.is_empty() || buckets == 0 {
+        return vec![0.0; buckets];
+    }
+
+    let size = samples.len().div_ceil(buckets);
+    let mut peaks: Vec<f32> = samples
+        .chunks(size)
+        .map(|chunk| {
+            let peak = chunk.iter().map(|s| s.unsigned_abs()).max().unwrap_or(0);
+            peak as f32 / i16::MAX as f32
+        })
+        .map(|peak| peak.min(1.0))
+        .collect();
+
+    // Short inputs can leave trailing buckets without samples
+    peaks.resize(buckets, 0.0);
+    peaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn it_computes_peaks() {
+        let pcm: Vec<u8> = [0i16, 16384, -32767, 100, 0, -8192]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+
+        let percent = |peaks: Vec<f32>| -> Vec<u32> {
+            peaks.iter().map(|p| (p * 100.0).round() as u32).collect()
+        };
+
+        assert_eq!(percent(peaks(&pcm, 3)), [50, 100, 25]);
+        assert_eq!(peaks(&pcm, 8).len(), 8);
+        assert_eq!(peaks(&[], 2), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn it_assembles() {
+        let mut waveform = Waveform {
+            input: String::from("videos/1.mov"),
+            width: 800,
+            height: 120,
+            output: PathBuf::from("cache/1.png"),
+            ffmpeg: Command::new("ffmpeg"),
+        };
+        let result: Vec<&OsStr> = waveform.assemble().get_args().collect();
+
+        assert_eq!(
+            result,
+            [
+                "-y",
+                "-i",
+                "videos/1.mov",
+                "-filter_complex",
+                "[0:a:0]aformat=channel_layouts=mono,showwavespic=s=800x120:colors=#22b8cf",
+                "-frames:v",
+                "1",
+                "cache/part-1.png"
+            ]
+        )
+    }
+}
@@ -8,10 +8,13 @@ import { useRef, useState } from "react";
 import "./App.css";
 import {
   AddButton,
+  ImportButton,
+  PersistProbesSwitch,
   ProcessButton,
   ResetButton,
 } from "./lib/components/ControlButtons";
 import Metadata from "./lib/components/Metadata";
+import { OptionsButton } from "./lib/components/Options";
 import Stacker from "./lib/components/Stacker";
 import { Trimmer, TrimmerButton, TrimmerText } from "./lib/components/Trimmer";
 
@@ -27,12 +30,16 @@ function clearElement<T>(
 }
 
 function StackManager(): React.JSX.Element {
-  const [items, setItems] = useState<{ id: string }[]>([]);
+  const [items, setItems] = useState<GridItem[]>([]);
   const [sliderValues, setSliderValue] = useState<SliderValues[]>([]);
   const [showSliders, setShowSlider] = useState<ElementMap>({});
   const [showMetadatas, setShowMetadatas] = useState<ElementMap>({});
   const [showTrimButtons, setShowTrimButtons] = useState<ElementMap>({});
   const [trimTexts, setTrimTexts] = useState<ElementMap>({});
+  const [showOptionButtons, setShowOptionButtons] = useState<ElementMap>({});
+  const [tileOptions, setTileOptions] = useState<{
+    [id: string]: TileOptions;
+  }>({});
   const [processResult, setProcessResult] = useState<ProcessResult | null>(
     null,
   );
@@ -55,6 +62,8 @@ function StackManager(): React.JSX.Element {
     setShowMetadatas({});
     setShowTrimButtons({});
     setTrimTexts({});
+    setShowOptionButtons({});
+    setTileOptions({});
     setProbes({});
     setProcessResult(null);
   };
@@ -86,6 +95,10 @@ function StackManager(): React.JSX.Element {
     }));
   };
 
+  const handleOptions = (id: string, options: TileOptions) => {
+    setTileOptions((prev) => ({ ...prev, [id]: options }));
+  };
+
   const clearSliderValue = (id: string) => {
     setSliderValue((prev) => prev.filter((slider) => slider.id !== id));
   };
@@ -105,16 +118,21 @@ function StackManager(): React.JSX.Element {
     }
 
     const result: ProbeResult = await invoke("probe", { input: path });
+    showProbe(id, result);
+  };
 
+  const showProbe = (id: string, result: ProbeResult) => {
     setProbes((prev) => ({
       ...prev,
       [id]: result,
     }));
 
-    // Clear sliders, values & text on new upload
+    // Clear sliders, values, text & options on new upload
     clearElement(id, setShowSlider);
     clearElement(id, setTrimTexts);
     clearSliderValue(id);
+    clearElement(id, setShowOptionButtons);
+    clearElement(id, setTileOptions);
 
     setShowMetadatas((prev) => ({
       ...prev,
@@ -132,18 +150,44 @@ function StackManager(): React.JSX.Element {
           ></TrimmerButton>
         ),
       }));
+      setShowOptionButtons((prev) => ({
+        ...prev,
+        [id]: (
+          <OptionsButton
+            key={result.probed.filename}
+            id={id}
+            probed={result.probed}
+            handleOptions={handleOptions}
+          ></OptionsButton>
+        ),
+      }));
     }
   };
 
+  const handleImport = (result: ImportResult) => {
+    resetItems();
+
+    // Imported tiles keep the spot the backend proposed for them
+    setItems(
+      result.positions.map(({ id, x, y }) => ({ id: id, x: x, y: y })),
+    );
+    inputs.current = result.sources.map(({ id, path }) => ({ id, path }));
+    result.sources.forEach(({ id, path }) =>
+      showProbe(id, result.probes[path]),
+    );
+  };
+
   const handleClearButton = (id: string) => {
     // Clear things from state first
     clearElement(id, setShowSlider);
     clearElement(id, setTrimTexts);
     clearSliderValue(id);
 
-    // Clear buttons
+    // Clear buttons & their options
     clearElement(id, setShowMetadatas);
     clearElement(id, setShowTrimButtons);
+    clearElement(id, setShowOptionButtons);
+    clearElement(id, setTileOptions);
 
     // Clear the probed value
     clearElement(id, setProbes);
@@ -165,15 +209,18 @@ function StackManager(): React.JSX.Element {
     <div>
       <Group justify="center">
         <AddButton addItem={addItem} />
+        <ImportButton handleImport={handleImport} />
         <ProcessButton
           gridRef={gridRef}
           inputs={inputs}
           sliderValues={sliderValues}
+          tileOptions={tileOptions}
           probes={probes}
           processResult={processResult}
           handleProcessResult={handleProcessResult}
         />
         <ResetButton resetItems={resetItems} />
+        <PersistProbesSwitch />
       </Group>
       <Stacker
         items={items}
@@ -181,6 +228,7 @@ function StackManager(): React.JSX.Element {
         trimTexts={trimTexts}
         showMetadatas={showMetadatas}
         showTrimButtons={showTrimButtons}
+        showOptionButtons={showOptionButtons}
         handleFileUpload={handleFileUpload}
         handleClearButton={handleClearButton}
         gridRef={gridRef}
@@ -1,21 +1,29 @@
-import { Button, Tooltip } from "@mantine/core";
+import { Button, Switch, Tooltip } from "@mantine/core";
 import { useDisclosure } from "@mantine/hooks";
 import {
+  IconAlertTriangle,
   IconCheck,
+  IconFolderOpen,
   IconPlayerPlay,
   IconPlus,
   IconReload,
   IconX,
 } from "@tabler/icons-react";
-import { open as TauriOpen } from "@tauri-apps/api/dialog";
+import { ask as TauriAsk, open as TauriOpen } from "@tauri-apps/api/dialog";
+import { listen as TauriListen } from "@tauri-apps/api/event";
 import { invoke as TauriInvoke } from "@tauri-apps/api/tauri";
 import { GridStack } from "gridstack";
+import { useEffect, useState } from "react";
 import { actionStyles } from "../styles";
 
 export interface AddButtonProp {
   addItem: () => void;
 }
 
+export interface ImportButtonProp {
+  handleImport: (result: ImportResult) => void;
+}
+
 export interface ResetButtonProp {
   resetItems: () => void;
 }
@@ -24,6 +32,7 @@ export interface ProcessButtonProp {
   gridRef: React.MutableRefObject<GridStack | null>;
   inputs: React.MutableRefObject<{ id: string; path: string }[]>;
   sliderValues: SliderValues[];
+  tileOptions: { [id: string]: TileOptions };
   probes: ProbeResults;
   processResult: ProcessResult | null;
   handleProcessResult: (result: ProcessResult) => void;
@@ -37,6 +46,54 @@ export function AddButton({ addItem }: AddButtonProp): React.JSX.Element {
   );
 }
 
+export function ImportButton({
+  handleImport,
+}: ImportButtonProp): React.JSX.Element {
+  const [loading, { open, close }] = useDisclosure();
+  const [message, setMessage] = useState<string | null>(null);
+
+  const importFolder = async () => {
+    const selected = await TauriOpen({
+      multiple: false,
+      directory: true,
+    });
+
+    if (!selected || Array.isArray(selected)) {
+      return;
+    }
+
+    open(); // Probing a whole folder takes a moment
+    const result: ImportResult = await TauriInvoke("import_folder", {
+      directory: selected,
+      recursive: false,
+    });
+    close();
+
+    setMessage(result.message);
+    if (result.status === "SUCCESS") {
+      handleImport(result);
+    }
+  };
+
+  return (
+    <Tooltip
+      label={message ?? "Fill the grid from a folder"}
+      transitionProps={{ transition: "fade-up", duration: 300 }}
+      color="gray"
+    >
+      <Button
+        {...actionStyles}
+        loading={loading}
+        loaderProps={{ type: "dots" }}
+        rightSection={<IconFolderOpen />}
+        onClick={importFolder}
+      >
+        Import
+      </Button>
+    </Tooltip>
+  );
+}
+
 export function ResetButton({
   resetItems,
 }: ResetButtonProp): React.JSX.Element {
@@ -51,6 +108,33 @@ export function ResetButton({
   );
 }
 
+// Setting key for persisting probes across launches, off unless the user opts in
+const PERSIST_PROBES_KEY = "persistProbes";
+
+export function PersistProbesSwitch(): React.JSX.Element {
+  const [enabled, setEnabled] = useState(
+    () => localStorage.getItem(PERSIST_PROBES_KEY) === "true",
+  );
+
+  // The backend starts without persistence, so apply the saved setting on launch
+  useEffect(() => {
+    TauriInvoke("persist_probes", { enabled: enabled });
+  }, [enabled]);
+
+  const handleChange = (checked: boolean) => {
+    localStorage.setItem(PERSIST_PROBES_KEY, `${checked}`);
+    setEnabled(checked);
+  };
+
+  return (
+    <Switch
+      label="Remember probes"
+      checked={enabled}
+      onChange={(event) => handleChange(event.currentTarget.checked)}
+    />
+  );
+}
+
 function statusDisplay(result: ProcessResult | null) {
   if (!result) {
     return {
@@ -64,12 +148,33 @@ function statusDisplay(result: ProcessResult | null) {
 
   switch (result.status) {
     case "SUCCESS":
+      if (result.warning) {
+        return {
+          label: `${label}. ${result.warning}`,
+          color: "yellow",
+          icon: <IconAlertTriangle />,
+        };
+      }
       return { label: label, color: "green", icon: <IconCheck /> };
     case "FAILED":
+    case "CONFLICT":
+    case "LARGE":
       return { label: label, color: "red", icon: <IconX /> };
   }
 }
 
+// Values of one option across the tiles, tagged with the id the backend matches them by
+function perTile<T extends object>(
+  inputs: { id: string }[],
+  tileOptions: { [id: string]: TileOptions },
+  pick: (options: TileOptions) => T | null,
+): (T & { id: string })[] {
+  return inputs.flatMap(({ id }) => {
+    const value = id in tileOptions ? pick(tileOptions[id]) : null;
+    return value ? [{ ...value, id: id }] : [];
+  });
+}
+
 function DisableProcess({ label }: { label: string }): React.JSX.Element {
   return (
     <Tooltip
@@ -93,12 +198,14 @@ export function ProcessButton({
   gridRef,
   inputs,
   sliderValues,
+  tileOptions,
   probes,
   processResult,
   handleProcessResult,
 }: ProcessButtonProp): React.JSX.Element {
   // For whatever reason this must be declared before any if conditions
   const [loading, { open, close }] = useDisclosure();
+  const [progress, setProgress] = useState<number | null>(null);
 
   if (inputs.current.length < 2) {
     const label = "A minimum of 2 inputs is required for processing!";
@@ -119,32 +226,113 @@ export function ProcessButton({
     });
 
     if (Array.isArray(selected)) {
-      return { status: "FAILED", message: "Multiple outputs selected" };
+      return {
+        status: "FAILED",
+        message: "Multiple outputs selected",
+        path: null,
+        warning: null,
+      };
     }
 
     if (!(selected && layout)) {
       return {
         status: "FAILED",
         message: "No layout found and/or no output selected",
+        path: null,
+        warning: null,
       };
     }
 
-    const probeValues = Object.values(probes).map((result) => result.probed);
+    // Reruns send back the path resolved by the first call, as `{date}` and `{time}`
+    // would resolve to another name than the one the user confirmed
+    let path = selected;
+    const process = async (
+      collision: Collision,
+      allowLarge = false,
+    ): Promise<ProcessResult> => {
+      const result: ProcessResult = await TauriInvoke("process", {
+        tiles: {
+          positions: layout,
+          sources: inputs.current,
+          sliders: sliderValues,
+          presets: perTile(inputs.current, tileOptions, (options) =>
+            options.presets.length ? { presets: options.presets } : null,
+          ),
+          crops: perTile(
+            inputs.current,
+            tileOptions,
+            (options) => options.crop,
+          ),
+          speeds: perTile(inputs.current, tileOptions, (options) =>
+            options.speed === null ? null : { factor: options.speed },
+          ),
+          playbacks: perTile(
+            inputs.current,
+            tileOptions,
+            (options) => options.playback,
+          ),
+          visuals: perTile(inputs.current, tileOptions, (options) =>
+            options.visualization
+              ? { visualization: options.visualization }
+              : null,
+          ),
+          captions: perTile(
+            inputs.current,
+            tileOptions,
+            (options) => options.subtitles,
+          ),
+        },
+        output: { path: path, collision: collision },
+        allowLarge: allowLarge,
+      });
+      path = result.path ?? path;
+      return result;
+    };
 
-    let result: ProcessResult = await TauriInvoke("process", {
-      positions: layout,
-      sources: inputs.current,
-      sliders: sliderValues,
-      probes: probeValues,
-      output: selected,
-    });
+    let collision: Collision = "ask";
+    let result = await process(collision);
+
+    if (result.status === "CONFLICT") {
+      // Only overwrite once the user has confirmed it
+      const overwrite = await TauriAsk(`${result.message}. Overwrite it?`, {
+        title: "Output exists",
+        type: "warning",
+      });
+
+      if (!overwrite) {
+        return { ...result, message: `Kept existing ${result.path}` };
+      }
+
+      collision = "overwrite";
+      result = await process(collision);
+    }
+
+    if (result.status === "LARGE") {
+      // Warned before rendering, as large animations take a while
+      const render = await TauriAsk(`${result.message}. Render it anyway?`, {
+        title: "Large output",
+        type: "warning",
+      });
+
+      if (!render) {
+        return { ...result, message: `Skipped rendering ${result.path}` };
+      }
+
+      result = await process(collision, true);
+    }
 
     return result;
   };
 
   const handleProcessStack = async () => {
     open(); // Start loading
+    const unlisten = await TauriListen<StackProgressEvent>(
+      "stack-progress",
+      (event) => setProgress(event.payload.progress),
+    );
     const result = await processStack();
+    unlisten();
+    setProgress(null);
     handleProcessResult(result);
     close(); // Stop loading after completion
   };
@@ -153,7 +341,11 @@ export function ProcessButton({
 
   return (
     <Tooltip
-      label={label}
+      label={
+        loading && progress !== null
+          ? `Processing: ${Math.round(progress * 100)}%`
+          : label
+      }
       transitionProps={{ transition: "fade-up", duration: 300 }}
       color="gray"
     >
@@ -3,7 +3,7 @@ import { IconArrowsMove, IconTrashX, IconUpload } from "@tabler/icons-react";
 import { open } from "@tauri-apps/api/dialog";
 import { MouseEvent } from "react";
 import { actionStyles, iconStyles } from "../styles";
-import { videoExtensions } from "./constants";
+import { audioExtensions, videoExtensions } from "./constants";
 
 export interface ItemProps {
   id: string;
@@ -11,6 +11,7 @@ export interface ItemProps {
   trimText: React.JSX.Element;
   showMetadata: React.JSX.Element;
   showTrimButton: React.JSX.Element;
+  showOptionButton: React.JSX.Element;
   handleFileUpload: (id: string, file: string | string[]) => void;
   handleClearButton: (id: string) => void;
 }
@@ -23,11 +24,15 @@ export default function Item({
   trimText,
   showMetadata,
   showTrimButton,
+  showOptionButton,
 }: ItemProps): React.JSX.Element {
   async function handler(_: MouseEvent<HTMLButtonElement>) {
     const selected = await open({
       multiple: false,
-      filters: [{ name: "Video", extensions: videoExtensions }],
+      filters: [
+        { name: "Video", extensions: videoExtensions },
+        { name: "Audio", extensions: audioExtensions },
+      ],
     });
 
     if (selected) {
@@ -47,6 +52,7 @@ export default function Item({
             ></IconArrowsMove>
           </ActionIcon>
           {showTrimButton}
+          {showOptionButton}
           {showMetadata}
           <ActionIcon {...actionStyles} onClick={handler}>
             <IconUpload {...iconStyles} />
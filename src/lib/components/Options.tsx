@@ -0,0 +1,229 @@
+import {
+  ActionIcon,
+  Group,
+  MultiSelect,
+  NumberInput,
+  Popover,
+  Select,
+  Stack,
+} from "@mantine/core";
+import { IconAdjustments } from "@tabler/icons-react";
+import { useState } from "react";
+import { actionStyles, iconStyles } from "../styles";
+
+export interface OptionsButtonProps {
+  id: string;
+  probed: Probed;
+  handleOptions: (id: string, options: TileOptions) => void;
+}
+
+const emptyOptions: TileOptions = {
+  presets: [],
+  crop: null,
+  speed: null,
+  playback: null,
+  visualization: null,
+  subtitles: null,
+};
+
+// Presets without settings of their own, applied in the order they were picked
+const presetChoices: { [value: string]: Preset } = {
+  hflip: { preset: "hflip" },
+  vflip: { preset: "vflip" },
+  "rotate 90": { preset: "rotate", degrees: 90 },
+  "rotate 180": { preset: "rotate", degrees: 180 },
+  "rotate 270": { preset: "rotate", degrees: 270 },
+  grayscale: { preset: "grayscale" },
+  denoise: { preset: "denoise" },
+  deshake: { preset: "deshake" },
+};
+
+const visualizations: Visualization[] = [
+  "waves",
+  "spectrum",
+  "vectorscope",
+  "loudness",
+];
+
+function toNumber(value: number | string): number | null {
+  return typeof value === "number" ? value : null;
+}
+
+export function OptionsButton({
+  id,
+  probed,
+  handleOptions,
+}: OptionsButtonProps): React.JSX.Element {
+  const [options, setOptions] = useState<TileOptions>(emptyOptions);
+  const [presets, setPresets] = useState<string[]>([]);
+  const [crop, setCrop] = useState({
+    x: 0,
+    y: 0,
+    width: probed.width,
+    height: probed.height,
+  });
+
+  const update = (changed: Partial<TileOptions>) => {
+    const next = { ...options, ...changed };
+    setOptions(next);
+    handleOptions(id, next);
+  };
+
+  const handlePresets = (values: string[]) => {
+    setPresets(values);
+    update({ presets: values.map((value) => presetChoices[value]) });
+  };
+
+  // The full frame is no crop at all
+  const handleCrop = (key: keyof typeof crop, value: number | string) => {
+    const next = { ...crop, [key]: toNumber(value) ?? 0 };
+    setCrop(next);
+
+    const full = next.width === probed.width && next.height === probed.height;
+    const empty = next.width === 0 || next.height === 0;
+    update({ crop: full || empty ? null : next });
+  };
+
+  const handlePlayback = (mode: string | null) => {
+    switch (mode) {
+      case "reverse":
+      case "boomerang":
+        update({ playback: { mode: mode } });
+        break;
+      case "loop":
+        update({ playback: { mode: "loop", count: 2 } });
+        break;
+      default:
+        update({ playback: null });
+    }
+  };
+
+  const handleLoops = (value: number | string) => {
+    update({ playback: { mode: "loop", count: toNumber(value) ?? 1 } });
+  };
+
+  const handleSubtitles = (value: number | string) => {
+    const index = toNumber(value);
+    update({
+      subtitles: index === null ? null : { source: "embedded", index: index },
+    });
+  };
+
+  return (
+    <Popover shadow="md" width={280} position="bottom" withArrow>
+      <Popover.Target>
+        <ActionIcon {...actionStyles}>
+          <IconAdjustments {...iconStyles} />
+        </ActionIcon>
+      </Popover.Target>
+      <Popover.Dropdown>
+        <Stack gap="xs">
+          {probed.audio_only ? (
+            <Select
+              label="Visualization"
+              size="xs"
+              clearable
+              data={visualizations}
+              value={options.visualization}
+              onChange={(value) =>
+                update({ visualization: value as Visualization | null })
+              }
+            />
+          ) : (
+            <>
+              <MultiSelect
+                label="Presets"
+                size="xs"
+                clearable
+                data={Object.keys(presetChoices)}
+                value={presets}
+                onChange={handlePresets}
+              />
+              <Group grow gap="xs">
+                <NumberInput
+                  label="Crop X"
+                  size="xs"
+                  min={0}
+                  max={probed.width}
+                  value={crop.x}
+                  onChange={(value) => handleCrop("x", value)}
+                />
+                <NumberInput
+                  label="Crop Y"
+                  size="xs"
+                  min={0}
+                  max={probed.height}
+                  value={crop.y}
+                  onChange={(value) => handleCrop("y", value)}
+                />
+              </Group>
+              <Group grow gap="xs">
+                <NumberInput
+                  label="Width"
+                  size="xs"
+                  min={0}
+                  max={probed.width}
+                  value={crop.width}
+                  onChange={(value) => handleCrop("width", value)}
+                />
+                <NumberInput
+                  label="Height"
+                  size="xs"
+                  min={0}
+                  max={probed.height}
+                  value={crop.height}
+                  onChange={(value) => handleCrop("height", value)}
+                />
+              </Group>
+              <NumberInput
+                label="Subtitle stream"
+                size="xs"
+                min={0}
+                allowDecimal={false}
+                value={
+                  options.subtitles?.source === "embedded"
+                    ? options.subtitles.index
+                    : ""
+                }
+                onChange={handleSubtitles}
+              />
+            </>
+          )}
+          <NumberInput
+            label="Speed"
+            size="xs"
+            min={0.25}
+            max={4}
+            step={0.25}
+            decimalScale={2}
+            value={options.speed ?? 1}
+            onChange={(value) => {
+              const factor = toNumber(value);
+              update({
+                speed: factor === null || factor === 1 ? null : factor,
+              });
+            }}
+          />
+          <Select
+            label="Playback"
+            size="xs"
+            clearable
+            data={["reverse", "loop", "boomerang"]}
+            value={options.playback?.mode ?? null}
+            onChange={handlePlayback}
+          />
+          {options.playback?.mode === "loop" && (
+            <NumberInput
+              label="Loops"
+              size="xs"
+              min={1}
+              allowDecimal={false}
+              value={options.playback.count}
+              onChange={handleLoops}
+            />
+          )}
+        </Stack>
+      </Popover.Dropdown>
+    </Popover>
+  );
+}
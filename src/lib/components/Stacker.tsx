@@ -3,10 +3,11 @@ import { createRef, RefObject, useEffect, useRef } from "react";
 import Item from "./Item";
 
 export interface StackerProps {
-  items: Array<{ id: string }>;
+  items: GridItem[];
   showSliders: ElementMap;
   showMetadatas: ElementMap;
   showTrimButtons: ElementMap;
+  showOptionButtons: ElementMap;
   trimTexts: ElementMap;
   handleFileUpload: (id: string, file: string | string[]) => void;
   handleClearButton: (id: string) => void;
@@ -19,6 +20,7 @@ export default function Stacker({
   trimTexts,
   showMetadatas,
   showTrimButtons,
+  showOptionButtons,
   handleFileUpload,
   handleClearButton,
   gridRef,
@@ -53,9 +55,9 @@ export default function Stacker({
     grid.batchUpdate();
     grid.removeAll(false); // Clear existing widgets
 
-    items.forEach(({ id }) => {
+    items.forEach(({ id, x, y }) => {
       if (refs.current[id]?.current) {
-        grid.makeWidget(refs.current[id].current!, { id: id }); // Add new widgets
+        grid.makeWidget(refs.current[id].current!, { id: id, x: x, y: y }); // Add new widgets
       }
     });
 
@@ -80,6 +82,7 @@ export default function Stacker({
                 showSlider={showSliders[item.id]}
                 showMetadata={showMetadatas[item.id]}
                 showTrimButton={showTrimButtons[item.id]}
+                showOptionButton={showOptionButtons[item.id]}
                 trimText={trimTexts[item.id]}
               />
             </div>
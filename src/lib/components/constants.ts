@@ -38,3 +38,6 @@ export const videoExtensions = [
   "f4b",
   "mod",
 ];
+
+// Audio-only inputs are stacked as visualizations
+export const audioExtensions = ["mp3", "wav", "flac", "m4a", "aac", "ogg", "opus"];
@@ -11,19 +11,173 @@ interface SliderValues {
   values: [number, number];
 }
 
+interface CropValues {
+  id: string;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+interface SpeedValues {
+  id: string;
+  factor: number;
+}
+
+type Playback =
+  | { mode: "reverse" }
+  | { mode: "loop"; count: number }
+  | { mode: "boomerang" };
+
+type PlaybackValues = Playback & { id: string };
+
+type Preset =
+  | { preset: "hflip" }
+  | { preset: "vflip" }
+  | { preset: "rotate"; degrees: 90 | 180 | 270 }
+  | {
+      preset: "eq";
+      brightness?: number;
+      contrast?: number;
+      saturation?: number;
+    }
+  | { preset: "denoise"; strength?: number }
+  | { preset: "deshake" }
+  | { preset: "grayscale" };
+
+type Visualization = "waves" | "spectrum" | "vectorscope" | "loudness";
+
+interface VisualValues {
+  id: string;
+  visualization: Visualization;
+}
+
+interface SubtitleStyle {
+  font?: string;
+  size?: number;
+  // #RRGGBB
+  color?: string;
+  outline?: number;
+  margin?: number;
+}
+
+type Subtitles = (
+  | { source: "embedded"; index: number }
+  | { source: "file"; path: string }
+) & { style?: SubtitleStyle };
+
+type Placement =
+  | "top-left"
+  | "top-right"
+  | "bottom-left"
+  | "bottom-right"
+  | { coordinates: { x: number; y: number } };
+
+interface Watermark {
+  path: string;
+  placement?: Placement;
+  // Fraction of the mosaic width
+  scale?: number;
+  opacity?: number;
+  margin?: number;
+}
+
+interface Metadata {
+  title?: string;
+  comment?: string;
+  // RFC 3339 timestamp or "now"
+  creation_time?: string;
+  video_title?: string;
+  audio_title?: string;
+  // Tag listing the inputs with their positions and trims, on by default
+  sources?: boolean;
+}
+
+type CaptionValues = Subtitles & { id: string };
+
+interface PresetValues {
+  id: string;
+  presets: Preset[];
+}
+
+// Per-tile choices made in the GUI, sent along as the optional `Tiles` values
+interface TileOptions {
+  presets: Preset[];
+  crop: Omit<CropValues, "id"> | null;
+  speed: number | null;
+  playback: Playback | null;
+  visualization: Visualization | null;
+  subtitles: Subtitles | null;
+}
+
 // Tauri serialised return types
 interface Probed {
   filename: string;
   duration: number;
   width: number;
   height: number;
+  audio: boolean;
+  audio_only: boolean;
 }
 
-type Status = "SUCCESS" | "FAILED";
+// "LARGE" asks to confirm an animation estimated too large before rendering it
+type Status = "SUCCESS" | "FAILED" | "CONFLICT" | "LARGE";
+
+// "ask" returns a CONFLICT result for an existing output instead of writing it
+type Collision = "increment" | "overwrite" | "fail" | "ask";
+
+type AnimatedFormat = "gif" | "webp" | "apng";
+
+type Dither =
+  | "bayer"
+  | "heckbert"
+  | "floyd_steinberg"
+  | "sierra2"
+  | "sierra2_4a"
+  | "none";
+
+interface Animation {
+  format: AnimatedFormat;
+  fps?: number;
+  width?: number;
+  dither?: Dither;
+  // 0 plays forever
+  plays?: number;
+}
+
+type Package = "hls" | "dash";
+
+interface Rendition {
+  height: number;
+  // Kilobits per second
+  bitrate?: number;
+}
+
+interface Segmenting {
+  package: Package;
+  segment_duration?: number;
+  renditions?: Rendition[];
+}
+
+interface Destination {
+  path: string;
+  collision: Collision;
+  animation?: Animation;
+  segmenting?: Segmenting;
+  // srt://, udp:// or rtmp:// URL, started with the stream command
+  streaming?: string;
+  // Burned over the mosaic, timed like the tile the audio comes from
+  subtitles?: Subtitles;
+  watermark?: Watermark;
+  metadata?: Metadata;
+}
 
 interface ProcessResult {
   status: Status;
   message: string;
+  // Output file, or the playlist of a segmented output
+  path: string | null;
+  warning: string | null;
 }
 
 interface ProbeResult {
@@ -31,3 +185,125 @@ interface ProbeResult {
   message: string;
   probed: Probed;
 }
+
+interface ExportResult {
+  status: Status;
+  message: string;
+  command: string;
+  warning: string | null;
+}
+
+interface BatchProbeEvent {
+  input: string;
+  result: ProbeResult;
+}
+
+interface Position {
+  id: string;
+  x: number;
+  y: number;
+}
+
+// Grid widget, placed by gridstack unless imported with a position
+interface GridItem {
+  id: string;
+  x?: number;
+  y?: number;
+}
+
+interface Source {
+  id: string;
+  path: string;
+}
+
+// Per-tile values sent to the backend, matched up by id
+interface Tiles {
+  positions: Position[];
+  sources: Source[];
+  sliders?: (SliderValues | null)[];
+  crops?: (CropValues | null)[];
+  speeds?: (SpeedValues | null)[];
+  playbacks?: (PlaybackValues | null)[];
+  presets?: (PresetValues | null)[];
+  visuals?: (VisualValues | null)[];
+  captions?: (CaptionValues | null)[];
+}
+
+interface ImportResult {
+  status: Status;
+  message: string;
+  positions: Position[];
+  sources: Source[];
+  probes: { [path: string]: ProbeResult };
+}
+
+type Strategy = "name" | "duration" | "created" | "resolution" | "portrait";
+
+type StackType = "x" | "horizontal" | "vertical";
+
+interface ArrangeResult {
+  status: Status;
+  message: string;
+  positions: Position[];
+  stack: StackType | null;
+}
+
+type ImageFormat = "jpeg" | "png";
+
+interface StackProgressEvent {
+  progress: number;
+}
+
+interface ThumbnailResult {
+  status: Status;
+  message: string;
+  paths: string[];
+}
+
+type Sampling = { mode: "even" } | { mode: "scenes"; threshold?: number };
+
+interface Sheet {
+  count?: number;
+  sampling?: Sampling;
+  width?: number;
+  timestamps?: boolean;
+  format?: ImageFormat;
+}
+
+interface StoryboardResult {
+  status: Status;
+  message: string;
+  path: string | null;
+}
+
+type WaveformMode = "image" | "peaks";
+
+interface WaveformResult {
+  status: Status;
+  message: string;
+  path: string | null;
+  peaks: number[];
+}
+
+interface Cut {
+  timestamp: number;
+  score: number;
+}
+
+interface ScenesResult {
+  status: Status;
+  message: string;
+  cuts: Cut[];
+}
+
+interface Silence {
+  start: number;
+  end: number;
+}
+
+interface SilencesResult {
+  status: Status;
+  message: string;
+  silences: Silence[];
+  suggested: [number, number] | null;
+}